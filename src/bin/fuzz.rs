@@ -1,8 +1,113 @@
+use brdgme_game::Gamer;
+use lost_cities::options::GameOptions;
 use lost_cities::Game;
-use brdgme_rand_bot::fuzz;
+use rand::{thread_rng, Rng};
 
-use std::io::stdout;
+use std::env;
+use std::io::{stdout, Write};
+use std::process;
 
+/// Games are abandoned after this many commands in case the random moves keep
+/// taking discards.
+const MAX_COMMANDS: usize = 10_000;
+/// How often to undo a move instead of making one.
+const UNDO_CHANCE: f64 = 0.05;
+/// How often to send a legal move rather than random words, so games finish.
+const LEGAL_CHANCE: f64 = 0.5;
+/// Words random commands are built from, along with every card and expedition.
+const WORDS: &[&str] = &["play", "discard", "take", "draw", "hint", ",", "", "x", "10", "0"];
+const REPORT_EVERY: usize = 100;
+
+/// Print the failing game so it can be replayed, then exit.
+fn fail(game: &Game, message: &str) -> ! {
+    eprintln!("{}", message);
+    match serde_json::to_string(&game.replay()) {
+        Ok(r) => eprintln!("replay: {}", r),
+        Err(e) => eprintln!("could not serialize replay: {}", e),
+    }
+    process::exit(1);
+}
+
+fn check(game: &Game) {
+    let violations = game.validate();
+    if !violations.is_empty() {
+        let messages: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+        fail(game, &format!("invalid state: {}", messages.join(", ")));
+    }
+}
+
+/// Build a command out of random words, cards and expeditions.
+fn random_command<R: Rng>(rng: &mut R, game: &Game) -> String {
+    let mut words: Vec<String> = WORDS.iter().map(|w| w.to_string()).collect();
+    words.extend(game.options.initial_deck().iter().map(|c| c.to_string()));
+    words.extend(game.options.expeditions().iter().map(|e| e.to_string()));
+    let len = rng.gen_range(1, 5);
+    (0..len)
+        .map(|_| {
+            let word = rng.choose(&words).unwrap();
+            if rng.gen() {
+                word.to_lowercase()
+            } else {
+                word.to_string()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Send random commands, mixed with legal moves so games progress, through the
+/// parser and validate the game after every step. Failed commands must leave
+/// the game untouched. Pass `rand-bot` to run the brdgme random bot fuzzer
+/// instead.
 fn main() {
-    fuzz::<Game, _>(&mut stdout());
+    if env::args().nth(1).as_deref() == Some("rand-bot") {
+        brdgme_rand_bot::fuzz::<Game, _>(&mut stdout());
+        return;
+    }
+    let mut rng = thread_rng();
+    let mut out = stdout();
+    let mut games: usize = 0;
+    let mut next_report = REPORT_EVERY;
+    loop {
+        let players = rng.gen_range(1, 4);
+        let mut options = GameOptions::for_players(players)
+            .unwrap_or_else(|e| panic!("could not create options: {}", e));
+        options.sixth_expedition = rng.gen();
+        let mut game = Game::new_with_options(players, options, rng.gen())
+            .unwrap_or_else(|e| panic!("could not create game: {}", e))
+            .0;
+        check(&game);
+        let mut commands = 0;
+        while !game.is_finished() && commands < MAX_COMMANDS {
+            let p = game.current_player;
+            if rng.gen::<f64>() < UNDO_CHANCE && game.history.last().map(|m| m.player) == Some(p) {
+                if game.undo(p).is_ok() {
+                    check(&game);
+                }
+                continue;
+            }
+            let command = if rng.gen::<f64>() < LEGAL_CHANCE {
+                match rng.choose(&game.legal_moves(p)) {
+                    Some(c) => c.to_string(),
+                    None => fail(&game, &format!("no legal moves for player {}", p)),
+                }
+            } else {
+                random_command(&mut rng, &game)
+            };
+            let before = game.clone();
+            match game.command(p, &command, &[]) {
+                Ok(_) => check(&game),
+                Err(_) if game != before => {
+                    fail(&game, &format!("failed command \"{}\" changed the game", command))
+                }
+                Err(_) => {}
+            }
+            commands += 1;
+        }
+        games += 1;
+        if games == next_report {
+            writeln!(out, "{} games played", games).unwrap();
+            next_report += REPORT_EVERY;
+        }
+    }
 }
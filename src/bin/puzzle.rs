@@ -0,0 +1,82 @@
+use brdgme_color::player_color;
+use brdgme_game::{Gamer, Renderer};
+use brdgme_markup::{ansi, transform, Player};
+use lost_cities::puzzle::Puzzle;
+use lost_cities::Game;
+
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::process;
+
+const USAGE: &str = "usage: puzzle FILE";
+
+fn load(path: &str) -> Result<Puzzle, String> {
+    let data = fs::read_to_string(path).map_err(|e| format!("could not read {}: {}", path, e))?;
+    serde_json::from_str(&data).map_err(|e| format!("could not parse {}: {}", path, e))
+}
+
+fn show(puzzle: &Puzzle, game: &Game, players: &[Player]) {
+    let nodes = game.player_state(puzzle.player).render();
+    println!("{}", ansi(&transform(&nodes, players)));
+    if puzzle.show_deck {
+        let deck: Vec<String> = game.deck.iter().map(|c| c.to_string()).collect();
+        println!("Deck from the top: {}", deck.join(" "));
+    }
+}
+
+fn main() {
+    let path = env::args().nth(1).unwrap_or_else(|| {
+        eprintln!("{}", USAGE);
+        process::exit(1);
+    });
+    let puzzle = load(&path).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    });
+    let mut game = puzzle.start().unwrap_or_else(|e| {
+        eprintln!("invalid puzzle: {}", e);
+        process::exit(1);
+    });
+    let players: Vec<Player> = (0..puzzle.position.players)
+        .map(|p| Player {
+            name: if p == puzzle.player {
+                "You".to_string()
+            } else {
+                format!("Player {}", p + 1)
+            },
+            color: player_color(p).to_owned(),
+        })
+        .collect();
+
+    println!("{}", puzzle.title);
+    if !puzzle.description.is_empty() {
+        println!("{}", puzzle.description);
+    }
+    println!("Goal: {}", puzzle.goal);
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        show(&puzzle, &game, &players);
+        if let Some(outcome) = puzzle.outcome(&game) {
+            println!("{}", outcome);
+            break;
+        }
+        print!("command, [r]estart or [q]uit: ");
+        io::stdout().flush().unwrap();
+        let line = match lines.next() {
+            Some(Ok(l)) => l,
+            _ => break,
+        };
+        match line.trim() {
+            "q" => break,
+            "r" => game = puzzle.start().expect("puzzle already started once"),
+            input => {
+                if let Err(e) = puzzle.command(&mut game, input) {
+                    println!("{}", e);
+                }
+            }
+        }
+    }
+}
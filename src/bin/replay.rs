@@ -0,0 +1,85 @@
+use brdgme_color::player_color;
+use brdgme_game::{Gamer, Renderer};
+use brdgme_markup::{ansi, transform, Player};
+use lost_cities::replay::Replay;
+use lost_cities::Game;
+
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::process;
+
+const USAGE: &str = "usage: replay FILE [PLAYER]";
+
+/// Load a replay, either saved directly or taken from the history of a saved
+/// game.
+fn load(path: &str) -> Result<Replay, String> {
+    let data = fs::read_to_string(path).map_err(|e| format!("could not read {}: {}", path, e))?;
+    serde_json::from_str::<Replay>(&data)
+        .or_else(|_| serde_json::from_str::<Game>(&data).map(|g| g.replay()))
+        .map_err(|e| format!("could not parse {}: {}", path, e))
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let path = args.get(1).unwrap_or_else(|| {
+        eprintln!("{}", USAGE);
+        process::exit(1);
+    });
+    let perspective: Option<usize> = args.get(2).map(|p| {
+        p.parse().unwrap_or_else(|_| {
+            eprintln!("{}", USAGE);
+            process::exit(1);
+        })
+    });
+    let replay = load(path).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    });
+    let states = replay.states().unwrap_or_else(|e| {
+        eprintln!("invalid replay: {}", e);
+        process::exit(1);
+    });
+    let players: Vec<Player> = (0..replay.players)
+        .map(|p| Player {
+            name: format!("Player {}", p + 1),
+            color: player_color(p).to_owned(),
+        })
+        .collect();
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    let mut step: usize = 0;
+    loop {
+        let game = &states[step];
+        let nodes = match perspective {
+            Some(p) if p < replay.players => game.player_state(p).render(),
+            _ => game.pub_state().render(),
+        };
+        println!("{}", ansi(&transform(&nodes, &players)));
+        match step.checked_sub(1).and_then(|s| replay.commands.get(s)) {
+            Some((p, command)) => println!(
+                "Step {} of {}, {} chose {}",
+                step,
+                replay.commands.len(),
+                players[*p].name,
+                command
+            ),
+            None => println!("Start of game, {} steps", replay.commands.len()),
+        }
+        print!("[n]ext, [p]revious, [f]irst, [l]ast, [q]uit: ");
+        io::stdout().flush().unwrap();
+        let line = match lines.next() {
+            Some(Ok(l)) => l,
+            _ => break,
+        };
+        match line.trim() {
+            "" | "n" => step = (step + 1).min(states.len() - 1),
+            "p" => step = step.saturating_sub(1),
+            "f" => step = 0,
+            "l" => step = states.len() - 1,
+            "q" => break,
+            other => println!("unknown input: {}", other),
+        }
+    }
+}
@@ -0,0 +1,267 @@
+use brdgme_game::Gamer;
+use lost_cities::bot::{Bot, HeuristicBot};
+use lost_cities::ismcts::{Budget, IsmctsBot};
+use lost_cities::options::GameOptions;
+use lost_cities::Game;
+
+use std::env;
+use std::process;
+
+const USAGE: &str = "usage: simulate [--games N] [--seed N] [--csv] [--six] [--rounds N] BOT BOT [BOT]
+
+Bots are `heuristic` or `ismcts[:ITERATIONS]`. Seats rotate every game so each
+bot starts equally often.";
+
+/// Games are abandoned after this many commands in case bots keep taking
+/// discards forever.
+const MAX_COMMANDS: usize = 10_000;
+const DEFAULT_GAMES: usize = 100;
+const DEFAULT_ISMCTS_ITERATIONS: usize = 1000;
+/// z value for a 95% confidence interval.
+const Z_95: f64 = 1.96;
+
+struct Config {
+    games: usize,
+    seed: u64,
+    csv: bool,
+    sixth_expedition: bool,
+    rounds: Option<usize>,
+    bots: Vec<String>,
+}
+
+/// Results for a single bot across every game it played.
+#[derive(Default)]
+struct Tally {
+    /// Wins count 1, shared wins are split between the winners.
+    wins: f64,
+    scores: Vec<f64>,
+    round_scores: Vec<Vec<f64>>,
+    expeditions: Vec<f64>,
+}
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(1);
+}
+
+fn parse_number<T: std::str::FromStr>(arg: Option<String>) -> T {
+    arg.and_then(|a| a.parse().ok()).unwrap_or_else(|| usage())
+}
+
+fn parse_args() -> Config {
+    let mut config = Config {
+        games: DEFAULT_GAMES,
+        seed: 0,
+        csv: false,
+        sixth_expedition: false,
+        rounds: None,
+        bots: vec![],
+    };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_ref() {
+            "--games" => config.games = parse_number(args.next()),
+            "--seed" => config.seed = parse_number(args.next()),
+            "--rounds" => config.rounds = Some(parse_number(args.next())),
+            "--csv" => config.csv = true,
+            "--six" => config.sixth_expedition = true,
+            "-h" | "--help" => usage(),
+            _ => config.bots.push(arg),
+        }
+    }
+    if config.bots.len() < 2 || config.bots.len() > 3 {
+        usage();
+    }
+    config
+}
+
+fn new_bot(spec: &str, seed: u64) -> Box<dyn Bot> {
+    let mut parts = spec.splitn(2, ':');
+    match (parts.next(), parts.next()) {
+        (Some("heuristic"), None) => Box::new(HeuristicBot),
+        (Some("ismcts"), iterations) => {
+            let iterations = match iterations {
+                Some(i) => i.parse().unwrap_or_else(|_| usage()),
+                None => DEFAULT_ISMCTS_ITERATIONS,
+            };
+            Box::new(IsmctsBot::with_seed(Budget::Iterations(iterations), seed))
+        }
+        _ => {
+            eprintln!("unknown bot: {}", spec);
+            usage();
+        }
+    }
+}
+
+fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// Sample variance.
+fn variance(values: &[f64]) -> f64 {
+    if values.len() < 2 {
+        return 0.0;
+    }
+    let m = mean(values);
+    values.iter().map(|v| (v - m).powi(2)).sum::<f64>() / (values.len() - 1) as f64
+}
+
+fn confidence(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    Z_95 * (variance(values) / values.len() as f64).sqrt()
+}
+
+fn main() {
+    let config = parse_args();
+    let players = config.bots.len();
+    let mut options = GameOptions::for_players(players).unwrap_or_else(|e| {
+        eprintln!("invalid number of bots: {}", e);
+        process::exit(1);
+    });
+    options.sixth_expedition = config.sixth_expedition;
+    if let Some(r) = config.rounds {
+        options.rounds = r;
+    }
+    if let Err(e) = options.validate(players) {
+        eprintln!("invalid options: {}", e);
+        process::exit(1);
+    }
+    let mut tallies: Vec<Tally> = (0..players)
+        .map(|_| Tally {
+            round_scores: vec![vec![]; options.rounds],
+            ..Tally::default()
+        })
+        .collect();
+    let mut abandoned = 0;
+
+    for g in 0..config.games {
+        let seed = config.seed.wrapping_add(g as u64);
+        // The bot in each seat, rotating so each bot starts equally often.
+        let seats: Vec<usize> = (0..players).map(|s| (s + g) % players).collect();
+        let mut bots: Vec<Box<dyn Bot>> = seats
+            .iter()
+            .map(|&b| new_bot(&config.bots[b], seed))
+            .collect();
+        let mut game = Game::new_with_options(players, options.clone(), seed)
+            .expect("options were validated")
+            .0;
+        let mut commands = 0;
+        while !game.is_finished() && commands < MAX_COMMANDS {
+            let p = game.current_player;
+            let input = bots[p].command(&game.player_state(p));
+            if let Err(e) = game.command(p, &input, &[]) {
+                eprintln!(
+                    "game {}: {} made an invalid command {}: {}",
+                    g, config.bots[seats[p]], input, e
+                );
+                process::exit(1);
+            }
+            commands += 1;
+        }
+        if !game.is_finished() {
+            abandoned += 1;
+            continue;
+        }
+        let public = game.pub_state();
+        let scores: Vec<isize> = (0..players).map(|p| public.player_score(p)).collect();
+        let best = scores.iter().cloned().max().unwrap_or(0);
+        let winners = scores.iter().filter(|&&s| s == best).count();
+        for (p, &b) in seats.iter().enumerate() {
+            let tally = &mut tallies[b];
+            if scores[p] == best {
+                tally.wins += 1.0 / winners as f64;
+            }
+            tally.scores.push(scores[p] as f64);
+            for (r, &rs) in game.scores[p].iter().enumerate() {
+                tally.round_scores[r].push(rs as f64);
+            }
+            tally.expeditions
+                .push(game.stats[p].expeditions_opened as f64 / options.rounds as f64);
+        }
+    }
+
+    let mut header: Vec<String> = vec![
+        "bot".to_string(),
+        "games".to_string(),
+        "win %".to_string(),
+        "win % ci".to_string(),
+        "score".to_string(),
+        "score ci".to_string(),
+        "variance".to_string(),
+    ];
+    for r in 1..=options.rounds {
+        header.push(format!("r{}", r));
+    }
+    header.push("expeditions".to_string());
+    let mut rows: Vec<Vec<String>> = vec![];
+    for (b, tally) in tallies.iter().enumerate() {
+        let n = tally.scores.len();
+        let win_rate = if n == 0 { 0.0 } else { tally.wins / n as f64 };
+        let win_ci = if n == 0 {
+            0.0
+        } else {
+            Z_95 * (win_rate * (1.0 - win_rate) / n as f64).sqrt()
+        };
+        let mut row = vec![
+            format!("{}:{}", b + 1, config.bots[b]),
+            format!("{}", n),
+            format!("{:.1}", win_rate * 100.0),
+            format!("{:.1}", win_ci * 100.0),
+            format!("{:.1}", mean(&tally.scores)),
+            format!("{:.1}", confidence(&tally.scores)),
+            format!("{:.1}", variance(&tally.scores)),
+        ];
+        for rs in &tally.round_scores {
+            row.push(format!("{:.1}", mean(rs)));
+        }
+        row.push(format!("{:.2}", mean(&tally.expeditions)));
+        rows.push(row);
+    }
+
+    if config.csv {
+        println!("{}", header.join(","));
+        for row in rows {
+            println!("{}", row.join(","));
+        }
+    } else {
+        let widths: Vec<usize> = (0..header.len())
+            .map(|i| {
+                rows.iter()
+                    .map(|r| r[i].len())
+                    .chain(Some(header[i].len()))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let format_row = |row: &[String]| -> String {
+            row.iter()
+                .zip(&widths)
+                .enumerate()
+                .map(|(i, (cell, &w))| {
+                    if i == 0 {
+                        format!("{:<w$}", cell, w = w)
+                    } else {
+                        format!("{:>w$}", cell, w = w)
+                    }
+                })
+                .collect::<Vec<String>>()
+                .join("  ")
+        };
+        println!("{}", format_row(&header));
+        for row in &rows {
+            println!("{}", format_row(row));
+        }
+        println!("95% confidence intervals, ci columns are the +/- margin.");
+    }
+    if abandoned > 0 {
+        eprintln!(
+            "{} games were abandoned after {} commands",
+            abandoned, MAX_COMMANDS
+        );
+    }
+}
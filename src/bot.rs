@@ -0,0 +1,401 @@
+use crate::card::{can_play, of_expedition, Card, Expedition, Value};
+use crate::command::Command;
+use crate::options::GameOptions;
+use crate::{Phase, PlayerState};
+
+use std::cmp;
+
+/// A bot which plays using only the information available to its player.
+pub trait Bot {
+    /// Choose the next command for the player the state belongs to.
+    fn choose(&mut self, state: &PlayerState) -> Command;
+
+    /// The next command as input for `Gamer::command`.
+    fn command(&mut self, state: &PlayerState) -> String {
+        self.choose(state).to_string()
+    }
+}
+
+/// How many turns of future draws we optimistically expect to help a new
+/// expedition, capped so we don't open everything early in the round.
+const MAX_OPEN_OPTIMISM: usize = 10;
+/// Penalty per card value skipped when playing to an expedition.
+const GAP_PENALTY: isize = 2;
+/// Minimum gain for taking a discard instead of drawing.
+const TAKE_THRESHOLD: isize = 4;
+/// Turns left per player at which we start timing the end of the round.
+const ENDGAME_TURNS: usize = 2;
+
+/// A rule based bot which only opens expeditions it expects to profit from,
+/// avoids discarding cards its opponents can use, and runs down the deck when
+/// ahead while stalling when behind.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct HeuristicBot;
+
+impl Bot for HeuristicBot {
+    fn choose(&mut self, state: &PlayerState) -> Command {
+        match state.public.phase {
+            Phase::PlayOrDiscard => play_or_discard(state),
+            Phase::DrawOrTake => draw_or_take(state),
+        }
+    }
+}
+
+/// A rough worth of a card, investments are worth a lot as they multiply the
+/// whole expedition.
+fn card_points(c: Card) -> isize {
+    match c.value {
+        Value::N(n) => n as isize,
+        Value::Investment => 10,
+    }
+}
+
+fn expedition_of(state: &PlayerState, player: usize) -> &[Card] {
+    state
+        .public
+        .expeditions
+        .get(player)
+        .map(|e| e.as_slice())
+        .unwrap_or(&[])
+}
+
+/// Including the current turn, roughly how many more cards we can play this
+/// round.
+fn turns_left(state: &PlayerState) -> usize {
+    state.public.deck_remaining / cmp::max(state.public.players, 1) + 1
+}
+
+/// The cards from a hand we could still play to an expedition, in the order we
+/// would play them.
+fn plan(expedition: &[Card], hand: &[Card], e: Expedition, turns: usize) -> Vec<Card> {
+    let mut cards: Vec<Card> = of_expedition(hand, e)
+        .into_iter()
+        .filter(|&c| can_play(expedition, c))
+        .collect();
+    cards.sort();
+    cards.truncate(turns);
+    cards
+}
+
+/// The best score an expedition could reach by playing out cards from a hand,
+/// leaving out investments if they would only multiply a loss.
+fn projected(
+    options: &GameOptions,
+    expedition: &[Card],
+    hand: &[Card],
+    e: Expedition,
+    turns: usize,
+) -> isize {
+    let current = of_expedition(expedition, e);
+    let mut with_inv = current.clone();
+    with_inv.extend(plan(expedition, hand, e, turns));
+    let numbers: Vec<Card> = hand.iter()
+        .filter(|c| c.value != Value::Investment)
+        .cloned()
+        .collect();
+    let mut without_inv = current;
+    without_inv.extend(plan(expedition, &numbers, e, turns));
+    cmp::max(options.score(&with_inv), options.score(&without_inv))
+}
+
+/// How much a card in hand is worth to us, either as part of an expedition we
+/// can profit from or as potential for a new one.
+fn keep_value(state: &PlayerState, c: Card) -> isize {
+    let options = &state.public.options;
+    let mine = expedition_of(state, state.player);
+    if !can_play(mine, c) {
+        return 0;
+    }
+    let turns = turns_left(state);
+    let mut without = state.hand.clone();
+    if let Some(i) = without.iter().position(|&hc| hc == c) {
+        without.remove(i);
+    }
+    let marginal = projected(options, mine, &state.hand, c.expedition, turns)
+        - projected(options, mine, &without, c.expedition, turns);
+    cmp::max(marginal, card_points(c) / 2)
+}
+
+/// How much a card would help the opponents if it were on the discard pile.
+fn danger(state: &PlayerState, c: Card) -> isize {
+    (0..state.public.players)
+        .filter(|&p| p != state.player)
+        .map(|p| {
+            let opp = expedition_of(state, p);
+            if !can_play(opp, c) {
+                0
+            } else if of_expedition(opp, c.expedition).is_empty() {
+                card_points(c) / 3
+            } else {
+                card_points(c) + 5
+            }
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Our total score including the current tableau, minus the best opponent.
+fn lead(state: &PlayerState) -> isize {
+    let options = &state.public.options;
+    let total = |p: usize| -> isize {
+        state.public.player_score(p) + options.score(expedition_of(state, p))
+    };
+    let best_opp = (0..state.public.players)
+        .filter(|&p| p != state.player)
+        .map(total)
+        .max()
+        .unwrap_or(0);
+    total(state.player) - best_opp
+}
+
+fn play_or_discard(state: &PlayerState) -> Command {
+    let options = &state.public.options;
+    let mine = expedition_of(state, state.player);
+    let turns = turns_left(state);
+    let mut best: Option<(isize, Card)> = None;
+    for e in options.expeditions() {
+        let next = match plan(mine, &state.hand, e, turns).first() {
+            Some(&c) => c,
+            None => continue,
+        };
+        let current = of_expedition(mine, e);
+        let mut worth = projected(options, mine, &state.hand, e, turns) - options.score(&current);
+        if current.is_empty() {
+            // We'll probably draw more cards for a new expedition, but it still
+            // needs to pay for itself.
+            worth += cmp::min(turns, MAX_OPEN_OPTIMISM) as isize;
+        }
+        if turns > ENDGAME_TURNS {
+            if let Value::N(n) = next.value {
+                let top = current
+                    .iter()
+                    .filter_map(|c| match c.value {
+                        Value::N(n) => Some(n as isize),
+                        Value::Investment => None,
+                    })
+                    .max()
+                    .unwrap_or(options.min_value as isize - 1);
+                worth -= (n as isize - top - 1) * GAP_PENALTY;
+            }
+        }
+        if worth > 0 && best.map(|(w, _)| worth > w).unwrap_or(true) {
+            best = Some((worth, next));
+        }
+    }
+    if let Some((_, c)) = best {
+        return Command::Play(c);
+    }
+    state
+        .hand
+        .iter()
+        .min_by_key(|&&c| (keep_value(state, c) + danger(state, c), card_points(c)))
+        .map(|&c| Command::Discard(c))
+        .unwrap_or(Command::Draw)
+}
+
+fn draw_or_take(state: &PlayerState) -> Command {
+    let options = &state.public.options;
+    let mine = expedition_of(state, state.player);
+    let turns = turns_left(state);
+    let mut best: Option<(isize, Expedition)> = None;
+    for e in options.expeditions() {
+        if state.public.discarded_expedition == Some(e) {
+            continue;
+        }
+        let top: Card = match state.public.discards.get(&e) {
+            Some(&v) => (e, v).into(),
+            None => continue,
+        };
+        let mut with = state.hand.clone();
+        with.push(top);
+        let gain = projected(options, mine, &with, e, turns)
+            - projected(options, mine, &state.hand, e, turns) + danger(state, top) / 2;
+        if best.map(|(g, _)| gain > g).unwrap_or(true) {
+            best = Some((gain, e));
+        }
+    }
+    match best {
+        Some((gain, e)) if gain >= TAKE_THRESHOLD => Command::Take(e),
+        // Stall the end of the round if we're behind.
+        Some((_, e)) if turns <= ENDGAME_TURNS + 1 && lead(state) < 0 => Command::Take(e),
+        _ => Command::Draw,
+    }
+}
+
+/// Suggest a command for the player along with a short reason, used for the
+/// hint command.
+pub fn hint(state: &PlayerState) -> (Command, String) {
+    let command = HeuristicBot.choose(state);
+    let reason = match command {
+        Command::Play(c) => play_reason(state, c),
+        Command::Discard(c) => discard_reason(state, c),
+        Command::Take(e) => take_reason(state, e),
+        Command::Draw => draw_reason(state),
+    };
+    (command, reason)
+}
+
+fn play_reason(state: &PlayerState, c: Card) -> String {
+    let options = &state.public.options;
+    let mine = expedition_of(state, state.player);
+    let projected = projected(options, mine, &state.hand, c.expedition, turns_left(state));
+    let name = c.expedition.name().to_lowercase();
+    if of_expedition(mine, c.expedition).is_empty() {
+        format!(
+            "{} starts your {} expedition, which could score {} with the cards you hold",
+            c, name, projected
+        )
+    } else {
+        format!(
+            "{} extends your {} expedition, which could score {} with the cards you hold",
+            c, name, projected
+        )
+    }
+}
+
+fn discard_reason(state: &PlayerState, c: Card) -> String {
+    let name = c.expedition.name().to_lowercase();
+    let opponents: Vec<usize> = (0..state.public.players)
+        .filter(|&p| p != state.player)
+        .collect();
+    if opponents
+        .iter()
+        .all(|&p| of_expedition(expedition_of(state, p), c.expedition).is_empty())
+    {
+        format!(
+            "discarding {} is safe because no opponent has started {}",
+            c, name
+        )
+    } else if opponents
+        .iter()
+        .all(|&p| !can_play(expedition_of(state, p), c))
+    {
+        format!("discarding {} is safe because no opponent can play it", c)
+    } else {
+        format!(
+            "{} is the card you can best afford to lose, though an opponent could use it",
+            c
+        )
+    }
+}
+
+fn take_reason(state: &PlayerState, e: Expedition) -> String {
+    let top: Card = match state.public.discards.get(&e) {
+        Some(&v) => (e, v).into(),
+        None => return draw_reason(state),
+    };
+    let mine = expedition_of(state, state.player);
+    if turns_left(state) <= ENDGAME_TURNS + 1 && lead(state) < 0 {
+        format!(
+            "taking {} keeps the deck from running out while you're behind",
+            top
+        )
+    } else if can_play(mine, top) && !of_expedition(mine, e).is_empty() {
+        format!(
+            "{} can be played on your {} expedition",
+            top,
+            e.name().to_lowercase()
+        )
+    } else {
+        format!("taking {} keeps it away from your opponents", top)
+    }
+}
+
+fn draw_reason(state: &PlayerState) -> String {
+    if turns_left(state) <= ENDGAME_TURNS + 1 && lead(state) >= 0 {
+        "drawing runs down the deck while you're ahead".to_string()
+    } else {
+        "none of the discards are worth taking".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Game;
+    use brdgme_game::Gamer;
+
+    /// Discards the first card in hand and draws, never scoring any points.
+    struct Discarder;
+
+    impl Bot for Discarder {
+        fn choose(&mut self, state: &PlayerState) -> Command {
+            match state.public.phase {
+                Phase::PlayOrDiscard => Command::Discard(state.hand[0]),
+                Phase::DrawOrTake => Command::Draw,
+            }
+        }
+    }
+
+    fn play_game(game: &mut Game, bots: &mut [&mut dyn Bot]) {
+        while !game.is_finished() {
+            let p = game.current_player;
+            let input = bots[p].command(&game.player_state(p));
+            game.command(p, &input, &[]).unwrap();
+        }
+    }
+
+    #[test]
+    fn heuristic_bot_finishes_games() {
+        for players in 2..4 {
+            for seed in 0..3 {
+                let mut game = Game::new_with_seed(players, seed).unwrap().0;
+                let mut bots: Vec<HeuristicBot> = vec![HeuristicBot; players];
+                let mut bot_refs: Vec<&mut dyn Bot> =
+                    bots.iter_mut().map(|b| b as &mut dyn Bot).collect();
+                play_game(&mut game, &mut bot_refs);
+            }
+        }
+    }
+
+    #[test]
+    fn heuristic_bot_beats_discarder() {
+        let mut wins = 0;
+        for seed in 0..10 {
+            let mut game = Game::new_with_seed(2, seed).unwrap().0;
+            play_game(&mut game, &mut [&mut HeuristicBot, &mut Discarder]);
+            if game.player_score(0) > game.player_score(1) {
+                wins += 1;
+            }
+        }
+        assert!(wins >= 7, "only won {} of 10 games", wins);
+    }
+
+    #[test]
+    fn hint_works() {
+        let mut game = Game::new_with_seed(2, 1).unwrap().0;
+        game.hands[0] = vec![
+            (Expedition::Red, Value::N(9)).into(),
+            (Expedition::White, Value::N(4)).into(),
+        ];
+        game.expeditions[1] = vec![(Expedition::Red, Value::N(4)).into()];
+        let (command, reason) = hint(&game.player_state(0));
+        assert_eq!(
+            Command::Discard((Expedition::White, Value::N(4)).into()),
+            command
+        );
+        assert_eq!(
+            "discarding W4 is safe because no opponent has started white",
+            reason
+        );
+    }
+
+    #[test]
+    fn avoids_feeding_opponent() {
+        let mut game = Game::new_with_seed(2, 1).unwrap().0;
+        game.hands[0] = vec![
+            (Expedition::Red, Value::N(7)).into(),
+            (Expedition::White, Value::N(3)).into(),
+        ];
+        // The opponent can still play W3 but is already past R7, so the
+        // higher card is the safer discard.
+        game.expeditions[1] = vec![
+            (Expedition::Red, Value::N(8)).into(),
+            (Expedition::White, Value::N(2)).into(),
+        ];
+        assert_eq!(
+            Command::Discard((Expedition::Red, Value::N(7)).into()),
+            HeuristicBot.choose(&game.player_state(0))
+        );
+    }
+}
@@ -18,6 +18,7 @@ pub enum Expedition {
     White,
     Blue,
     Yellow,
+    Purple,
 }
 
 impl Expedition {
@@ -28,6 +29,18 @@ impl Expedition {
             Expedition::White => brdgme_color::GREY,
             Expedition::Blue => brdgme_color::BLUE,
             Expedition::Yellow => brdgme_color::AMBER,
+            Expedition::Purple => brdgme_color::PURPLE,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Expedition::Red => "Red",
+            Expedition::Green => "Green",
+            Expedition::White => "White",
+            Expedition::Blue => "Blue",
+            Expedition::Yellow => "Yellow",
+            Expedition::Purple => "Purple",
         }
     }
 
@@ -38,6 +51,7 @@ impl Expedition {
             Expedition::White => "W".to_string(),
             Expedition::Blue => "B".to_string(),
             Expedition::Yellow => "Y".to_string(),
+            Expedition::Purple => "P".to_string(),
         }
     }
 }
@@ -71,6 +85,14 @@ pub fn expeditions() -> Vec<Expedition> {
     ]
 }
 
+/// All expeditions including purple, which is only used when playing with six
+/// expeditions.
+pub fn all_expeditions() -> Vec<Expedition> {
+    let mut exps = expeditions();
+    exps.push(Expedition::Purple);
+    exps
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Card {
     pub expedition: Expedition,
@@ -94,7 +116,7 @@ impl From<(Expedition, Value)> for Card {
 
 pub fn by_expedition(cards: &[Card]) -> HashMap<Expedition, Vec<Card>> {
     let mut output: HashMap<Expedition, Vec<Card>> = HashMap::new();
-    for e in expeditions() {
+    for e in all_expeditions() {
         output.insert(e, of_expedition(cards, e));
     }
     output
@@ -116,6 +138,24 @@ pub fn last_expedition(cards: &[Card], expedition: Expedition) -> Option<Card> {
         .cloned()
 }
 
+/// Whether a card can be played to a player's expeditions, numbers must be
+/// ascending and investments must come before any numbers.
+pub fn can_play(expeditions: &[Card], c: Card) -> bool {
+    let highest = expeditions
+        .iter()
+        .filter(|ec| ec.expedition == c.expedition)
+        .filter_map(|ec| match ec.value {
+            Value::N(n) => Some(n),
+            Value::Investment => None,
+        })
+        .max();
+    match (highest, c.value) {
+        (None, _) => true,
+        (Some(h), Value::N(n)) => n > h,
+        (Some(_), Value::Investment) => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
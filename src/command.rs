@@ -1,11 +1,16 @@
+use serde_derive::{Deserialize, Serialize};
+
 use brdgme_game::command::parser::*;
-use brdgme_game::Gamer;
+use brdgme_game::errors::GameError;
+use brdgme_game::{Gamer, Log};
 
-use crate::card::{expeditions, Card, Expedition};
+use crate::card::{Card, Expedition};
 use crate::Game;
 use crate::Phase;
 
-#[derive(Debug, PartialEq, Copy, Clone)]
+use std::fmt;
+
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
 pub enum Command {
     Play(Card),
     Discard(Card),
@@ -13,29 +18,151 @@ pub enum Command {
     Draw,
 }
 
+/// What a player can type, either a move or asking for a hint which doesn't
+/// change the game.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Input {
+    Move(Command),
+    Hint,
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Command::Play(c) => write!(f, "play {}", c),
+            Command::Discard(c) => write!(f, "discard {}", c),
+            Command::Take(e) => write!(f, "take {}", e),
+            Command::Draw => write!(f, "draw"),
+        }
+    }
+}
+
+impl Command {
+    /// Draws reveal a card so can't be undone.
+    pub fn can_undo(&self) -> bool {
+        match *self {
+            Command::Play(_) | Command::Discard(_) | Command::Take(_) => true,
+            Command::Draw => false,
+        }
+    }
+
+    /// Whether the command starts a turn, in which case it can be followed by
+    /// a draw or take in the same input.
+    pub fn starts_turn(&self) -> bool {
+        match *self {
+            Command::Play(_) | Command::Discard(_) => true,
+            Command::Take(_) | Command::Draw => false,
+        }
+    }
+}
+
+/// The rest of a whole turn given in a single input, such as `play r5 draw` or
+/// `discard g3, take b`.
+pub fn turn_rest(remaining: &str) -> Option<&str> {
+    let rest = remaining.trim_start();
+    let rest = rest.strip_prefix(',').unwrap_or(rest).trim_start();
+    if rest.is_empty() {
+        None
+    } else {
+        Some(rest)
+    }
+}
+
 impl Game {
-    pub fn command_parser(&self, player: usize) -> Option<Box<Parser<Command>>> {
-        if self.is_finished() {
-            return None;
+    /// Every command the player can currently make, respecting the phase, the
+    /// ascending order of expeditions and not taking back a discard.
+    pub fn legal_moves(&self, player: usize) -> Vec<Command> {
+        let mut moves: Vec<Command> = vec![];
+        if self.is_finished() || self.current_player != player {
+            return moves;
         }
-        let mut parsers: Vec<Box<Parser<Command>>> = vec![];
-        if self.current_player == player {
-            match self.phase {
-                Phase::PlayOrDiscard => {
-                    parsers.push(Box::new(self.play_parser(player)));
-                    parsers.push(Box::new(self.discard_parser(player)));
+        match self.phase {
+            Phase::PlayOrDiscard => {
+                let mut hand = self.hands.get(player).cloned().unwrap_or_default();
+                hand.sort();
+                hand.dedup();
+                for &c in &hand {
+                    if self.can_play(player, c) {
+                        moves.push(Command::Play(c));
+                    }
+                }
+                for &c in &hand {
+                    moves.push(Command::Discard(c));
                 }
-                Phase::DrawOrTake => {
-                    parsers.push(Box::new(draw_parser()));
-                    parsers.push(Box::new(take_parser()));
+            }
+            Phase::DrawOrTake => {
+                moves.push(Command::Draw);
+                for e in self.options.expeditions() {
+                    if self.discarded_expedition != Some(e) && self.available_discard(e).is_some() {
+                        moves.push(Command::Take(e));
+                    }
                 }
             }
         }
-        if parsers.is_empty() {
-            None
-        } else {
-            Some(Box::new(OneOf::new(parsers)))
+        moves
+    }
+
+    /// Run a command for a player directly, without parsing.
+    pub(crate) fn apply_command(
+        &mut self,
+        player: usize,
+        command: Command,
+    ) -> Result<Vec<Log>, GameError> {
+        match command {
+            Command::Play(c) => self.play(player, c),
+            Command::Discard(c) => self.discard(player, c),
+            Command::Take(e) => self.take(player, e),
+            Command::Draw => self.draw(player),
+        }
+    }
+
+    /// Parse a single command for the player, returning it with the remaining
+    /// input.
+    pub fn parse_command(
+        &self,
+        player: usize,
+        input: &str,
+        players: &[String],
+    ) -> Result<(Command, String), GameError> {
+        let cp = self.command_parser(player)
+            .ok_or_else(|| GameError::invalid_input("not your turn"))?;
+        let output = cp.parse(input, players)?;
+        Ok((output.value, output.remaining.to_string()))
+    }
+
+    pub fn command_parser(&self, player: usize) -> Option<Box<Parser<Command>>> {
+        self.move_parsers(player)
+            .map(|parsers| Box::new(OneOf::new(parsers)) as Box<Parser<Command>>)
+    }
+
+    /// Parses everything a player can type, which is any move along with
+    /// asking for a hint.
+    pub fn input_parser(&self, player: usize) -> Option<Box<Parser<Input>>> {
+        self.move_parsers(player).map(|parsers| {
+            let inputs: Vec<Box<Parser<Input>>> = vec![
+                Box::new(Map::new(OneOf::new(parsers), Input::Move)),
+                Box::new(hint_parser()),
+            ];
+            Box::new(OneOf::new(inputs)) as Box<Parser<Input>>
+        })
+    }
+
+    fn move_parsers(&self, player: usize) -> Option<Vec<Box<Parser<Command>>>> {
+        if self.is_finished() || self.current_player != player {
+            return None;
+        }
+        let mut parsers: Vec<Box<Parser<Command>>> = vec![];
+        match self.phase {
+            Phase::PlayOrDiscard => {
+                parsers.push(Box::new(self.play_parser(player)));
+                parsers.push(Box::new(self.discard_parser(player)));
+            }
+            Phase::DrawOrTake => {
+                parsers.push(Box::new(draw_parser()));
+                parsers.push(Box::new(take_parser(self.options.expeditions())));
+            }
         }
+        Some(parsers)
     }
 
     pub fn play_parser(&self, player: usize) -> impl Parser<Command> {
@@ -63,7 +190,7 @@ impl Game {
     }
 
     pub fn player_card_parser(&self, player: usize, desc: &str) -> impl Parser<Card> {
-        let mut player_hand = self.hands.get(player).cloned().unwrap_or_else(|| vec![]);
+        let mut player_hand = self.hands.get(player).cloned().unwrap_or_default();
         player_hand.sort();
         player_hand.dedup();
         Doc::name_desc("card", desc, Enum::exact(player_hand))
@@ -78,7 +205,15 @@ pub fn draw_parser() -> impl Parser<Command> {
     )
 }
 
-pub fn take_parser() -> impl Parser<Command> {
+pub fn hint_parser() -> impl Parser<Input> {
+    Doc::name_desc(
+        "hint",
+        "suggest a move for your turn, without making it",
+        Map::new(Token::new("hint"), |_| Input::Hint),
+    )
+}
+
+pub fn take_parser(expeditions: Vec<Expedition>) -> impl Parser<Command> {
     Map::new(
         Chain2::new(
             Doc::name_desc(
@@ -86,16 +221,134 @@ pub fn take_parser() -> impl Parser<Command> {
                 "take the top card from one of the common discard piles",
                 Token::new("take"),
             ),
-            AfterSpace::new(expedition_parser()),
+            AfterSpace::new(expedition_parser(expeditions)),
         ),
         |(_, e)| Command::Take(e),
     )
 }
 
-pub fn expedition_parser() -> impl Parser<Expedition> {
+pub fn expedition_parser(expeditions: Vec<Expedition>) -> impl Parser<Expedition> {
     Doc::name_desc(
         "expedition",
         "the expedition to take from",
-        Enum::exact(expeditions()),
+        Enum::exact(expeditions),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::Value;
+
+    #[test]
+    fn legal_moves_works() {
+        let mut game = Game::new_with_seed(2, 1).unwrap().0;
+        assert!(game.legal_moves(1).is_empty());
+        game.hands[0] = vec![
+            (Expedition::Green, Value::Investment).into(),
+            (Expedition::Green, Value::Investment).into(),
+            (Expedition::Green, Value::N(3)).into(),
+            (Expedition::Green, Value::N(7)).into(),
+            (Expedition::Red, Value::N(2)).into(),
+        ];
+        game.expeditions[0] = vec![(Expedition::Green, Value::N(5)).into()];
+        assert_eq!(
+            vec![
+                Command::Play((Expedition::Red, Value::N(2)).into()),
+                Command::Play((Expedition::Green, Value::N(7)).into()),
+                Command::Discard((Expedition::Red, Value::N(2)).into()),
+                Command::Discard((Expedition::Green, Value::Investment).into()),
+                Command::Discard((Expedition::Green, Value::N(3)).into()),
+                Command::Discard((Expedition::Green, Value::N(7)).into()),
+            ],
+            game.legal_moves(0)
+        );
+        // Can't take back the card we just discarded.
+        game.discard(0, (Expedition::Green, Value::N(3)).into())
+            .unwrap();
+        assert_eq!(vec![Command::Draw], game.legal_moves(0));
+        game.draw(0).unwrap();
+        // The next player can take it.
+        assert!(game.legal_moves(0).is_empty());
+        let c = game.hands[1][0];
+        game.play(1, c).unwrap();
+        assert_eq!(
+            vec![Command::Draw, Command::Take(Expedition::Green)],
+            game.legal_moves(1)
+        );
+    }
+
+    #[test]
+    fn command_display_works() {
+        assert_eq!(
+            "play G7",
+            Command::Play((Expedition::Green, Value::N(7)).into()).to_string()
+        );
+        assert_eq!(
+            "discard RX",
+            Command::Discard((Expedition::Red, Value::Investment).into()).to_string()
+        );
+        assert_eq!("take B", Command::Take(Expedition::Blue).to_string());
+        assert_eq!("draw", Command::Draw.to_string());
+    }
+
+    #[test]
+    fn combined_turn_works() {
+        let mut game = Game::new_with_seed(2, 1).unwrap().0;
+        game.hands[0][0] = (Expedition::Red, Value::N(5)).into();
+        game.hands[0][1] = (Expedition::Green, Value::N(3)).into();
+        game.hands[1][0] = (Expedition::Blue, Value::N(4)).into();
+        let resp = game.command(0, "play r5 draw", &[]).unwrap();
+        assert!(!resp.can_undo);
+        assert_eq!("", resp.remaining_input);
+        assert_eq!(1, game.current_player);
+        assert_eq!(8, game.hands[0].len());
+        assert_eq!(
+            vec![Card::from((Expedition::Red, Value::N(5)))],
+            game.expeditions[0]
+        );
+        game.command(1, "discard b4, take r", &[]).unwrap_err();
+        game.command(1, "discard b4, draw", &[]).unwrap();
+        game.command(0, "discard g3", &[]).unwrap();
+        let resp = game.command(0, "take b", &[]).unwrap();
+        assert!(resp.can_undo);
+        assert_eq!(1, game.current_player);
+    }
+
+    #[test]
+    fn hint_works() {
+        let mut game = Game::new_with_seed(2, 1).unwrap().0;
+        let before = game.clone();
+        let resp = game.command(0, "hint", &[]).unwrap();
+        assert_eq!(before, game);
+        assert!(!resp.can_undo);
+        assert_eq!(1, resp.logs.len());
+        assert!(!resp.logs[0].public);
+        assert_eq!(vec![0], resp.logs[0].to);
+        assert!(game.command(1, "hint", &[]).is_err());
+        let c = game.hands[0][0];
+        game.discard(0, c).unwrap();
+        let before = game.clone();
+        game.command(0, "hint", &[]).unwrap();
+        assert_eq!(before, game);
+        // A hint isn't a move, so can't finish a turn.
+        assert!(game.parse_command(0, "hint", &[]).is_err());
+        let mut game = Game::new_with_seed(2, 1).unwrap().0;
+        let before = game.clone();
+        let c = game.hands[0][0];
+        assert!(game.command(0, &format!("discard {}, hint", c), &[]).is_err());
+        assert_eq!(before, game);
+    }
+
+    #[test]
+    fn combined_turn_is_atomic() {
+        let mut game = Game::new_with_seed(2, 1).unwrap().0;
+        game.hands[0][0] = (Expedition::Red, Value::N(5)).into();
+        let before = game.clone();
+        // Can't take back the card just discarded.
+        assert!(game.command(0, "discard r5, take r", &[]).is_err());
+        assert_eq!(before, game);
+        assert!(game.command(0, "play r5 play r5", &[]).is_err());
+        assert_eq!(before, game);
+    }
+}
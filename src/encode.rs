@@ -0,0 +1,248 @@
+//! A fixed length numeric encoding of what a player can see, and a mapping
+//! between commands and action indices, for training agents against the game.
+//!
+//! The layout is versioned by `VERSION`, any change to it must bump the
+//! version. Cards are encoded in 12 slots per expedition: three investment
+//! slots followed by the values 2 to 10. The nth investment of an expedition
+//! in a set of cards fills the nth investment slot. All six expeditions are
+//! always encoded, purple is left empty unless playing with six expeditions.
+//! Cards outside the standard values are left out.
+//!
+//! Observation layout for version 1, players are ordered from the observer
+//! onwards in turn order and padded to three players:
+//!
+//! | Offset | Size | Contents                                             |
+//! |--------|------|------------------------------------------------------|
+//! | 0      | 72   | Hand                                                 |
+//! | 72     | 216  | Tableau for each player                              |
+//! | 288    | 72   | Top card of each discard pile                        |
+//! | 360    | 6    | Discard pile sizes, divided by 12                    |
+//! | 366    | 1    | Cards left in the deck, divided by the deck size     |
+//! | 367    | 2    | Phase, play or discard then draw or take             |
+//! | 369    | 1    | Rounds completed, divided by the number of rounds    |
+//! | 370    | 1    | Score minus the best opponent score, divided by 100  |
+//!
+//! Action layout, with 10 card types per expedition: investment then the
+//! values 2 to 10.
+//!
+//! | Offset | Size | Action                     |
+//! |--------|------|----------------------------|
+//! | 0      | 60   | Play a card                |
+//! | 60     | 60   | Discard a card             |
+//! | 120    | 1    | Draw                       |
+//! | 121    | 6    | Take from a discard pile   |
+
+use crate::card::{all_expeditions, Card, Expedition, Value};
+use crate::command::Command;
+use crate::{Phase, PlayerState, INVESTMENTS, MAX_PLAYERS, MAX_VALUE, MIN_VALUE, START_ROUND};
+
+/// The version of the observation and action layouts.
+pub const VERSION: u32 = 1;
+
+const EXPEDITIONS: usize = 6;
+const INVESTMENT_SLOTS: usize = INVESTMENTS;
+const SLOTS_PER_EXPEDITION: usize = INVESTMENT_SLOTS + MAX_VALUE - MIN_VALUE + 1;
+const TYPES_PER_EXPEDITION: usize = 1 + MAX_VALUE - MIN_VALUE + 1;
+/// The most cards a discard pile can hold, every card of one expedition in the
+/// standard deck.
+const MAX_DISCARD_PILE: usize = INVESTMENTS + MAX_VALUE - MIN_VALUE + 1;
+const SCORE_SCALE: f32 = 100.0;
+
+/// The size of an encoded set of cards.
+pub const CARD_SLOTS: usize = EXPEDITIONS * SLOTS_PER_EXPEDITION;
+
+const HAND_OFFSET: usize = 0;
+const TABLEAU_OFFSET: usize = HAND_OFFSET + CARD_SLOTS;
+const DISCARD_TOP_OFFSET: usize = TABLEAU_OFFSET + MAX_PLAYERS * CARD_SLOTS;
+const DISCARD_SIZE_OFFSET: usize = DISCARD_TOP_OFFSET + CARD_SLOTS;
+const DECK_OFFSET: usize = DISCARD_SIZE_OFFSET + EXPEDITIONS;
+const PHASE_OFFSET: usize = DECK_OFFSET + 1;
+const ROUND_OFFSET: usize = PHASE_OFFSET + 2;
+const SCORE_OFFSET: usize = ROUND_OFFSET + 1;
+
+/// The length of an encoded observation.
+pub const OBSERVATION_SIZE: usize = SCORE_OFFSET + 1;
+
+const CARD_TYPES: usize = EXPEDITIONS * TYPES_PER_EXPEDITION;
+const PLAY_OFFSET: usize = 0;
+const DISCARD_OFFSET: usize = PLAY_OFFSET + CARD_TYPES;
+const DRAW_OFFSET: usize = DISCARD_OFFSET + CARD_TYPES;
+const TAKE_OFFSET: usize = DRAW_OFFSET + 1;
+
+/// The number of actions.
+pub const ACTION_SIZE: usize = TAKE_OFFSET + EXPEDITIONS;
+
+fn expedition_index(e: Expedition) -> usize {
+    all_expeditions()
+        .iter()
+        .position(|&ae| ae == e)
+        .expect("expedition missing from all_expeditions")
+}
+
+fn value_index(v: Value) -> Option<usize> {
+    match v {
+        Value::Investment => Some(0),
+        Value::N(n) if (MIN_VALUE..=MAX_VALUE).contains(&n) => Some(n - MIN_VALUE + 1),
+        Value::N(_) => None,
+    }
+}
+
+fn card_type_index(c: Card) -> Option<usize> {
+    value_index(c.value).map(|v| expedition_index(c.expedition) * TYPES_PER_EXPEDITION + v)
+}
+
+fn card_type(index: usize) -> Option<Card> {
+    if index >= CARD_TYPES {
+        return None;
+    }
+    let e = all_expeditions()[index / TYPES_PER_EXPEDITION];
+    let value = match index % TYPES_PER_EXPEDITION {
+        0 => Value::Investment,
+        v => Value::N(v - 1 + MIN_VALUE),
+    };
+    Some((e, value).into())
+}
+
+/// Encode a set of cards into `out`, which must be `CARD_SLOTS` long.
+pub fn encode_cards(cards: &[Card], out: &mut [f32]) {
+    for c in cards {
+        let base = expedition_index(c.expedition) * SLOTS_PER_EXPEDITION;
+        match c.value {
+            Value::Investment => {
+                if let Some(slot) = (base..base + INVESTMENT_SLOTS).find(|&s| out[s] == 0.0) {
+                    out[slot] = 1.0;
+                }
+            }
+            Value::N(n) if (MIN_VALUE..=MAX_VALUE).contains(&n) => {
+                out[base + INVESTMENT_SLOTS + n - MIN_VALUE] = 1.0;
+            }
+            Value::N(_) => {}
+        }
+    }
+}
+
+/// Encode what the player can see as a vector of `OBSERVATION_SIZE` numbers.
+pub fn encode(state: &PlayerState) -> Vec<f32> {
+    let public = &state.public;
+    let mut out = vec![0.0; OBSERVATION_SIZE];
+    encode_cards(&state.hand, &mut out[HAND_OFFSET..HAND_OFFSET + CARD_SLOTS]);
+    for offset in 0..public.players.min(MAX_PLAYERS) {
+        let p = (state.player + offset) % public.players;
+        if let Some(e) = public.expeditions.get(p) {
+            let start = TABLEAU_OFFSET + offset * CARD_SLOTS;
+            encode_cards(e, &mut out[start..start + CARD_SLOTS]);
+        }
+    }
+    let tops: Vec<Card> = public
+        .discards
+        .iter()
+        .map(|(&e, &v)| (e, v).into())
+        .collect();
+    encode_cards(
+        &tops,
+        &mut out[DISCARD_TOP_OFFSET..DISCARD_TOP_OFFSET + CARD_SLOTS],
+    );
+    for (&e, &size) in &public.discard_sizes {
+        out[DISCARD_SIZE_OFFSET + expedition_index(e)] = size as f32 / MAX_DISCARD_PILE as f32;
+    }
+    out[DECK_OFFSET] = public.deck_remaining as f32 / public.options.deck_size().max(1) as f32;
+    out[PHASE_OFFSET + match public.phase {
+        Phase::PlayOrDiscard => 0,
+        Phase::DrawOrTake => 1,
+    }] = 1.0;
+    out[ROUND_OFFSET] =
+        public.round.saturating_sub(START_ROUND) as f32 / public.options.rounds.max(1) as f32;
+    let best_opp = (0..public.players)
+        .filter(|&p| p != state.player)
+        .map(|p| public.player_score(p))
+        .max()
+        .unwrap_or(0);
+    out[SCORE_OFFSET] = (public.player_score(state.player) - best_opp) as f32 / SCORE_SCALE;
+    out
+}
+
+/// The action index of a command, cards outside the standard values don't
+/// have one.
+pub fn action_index(command: Command) -> Option<usize> {
+    match command {
+        Command::Play(c) => card_type_index(c).map(|i| PLAY_OFFSET + i),
+        Command::Discard(c) => card_type_index(c).map(|i| DISCARD_OFFSET + i),
+        Command::Draw => Some(DRAW_OFFSET),
+        Command::Take(e) => Some(TAKE_OFFSET + expedition_index(e)),
+    }
+}
+
+/// The command for an action index.
+pub fn action(index: usize) -> Option<Command> {
+    if index < DISCARD_OFFSET {
+        card_type(index - PLAY_OFFSET).map(Command::Play)
+    } else if index < DRAW_OFFSET {
+        card_type(index - DISCARD_OFFSET).map(Command::Discard)
+    } else if index == DRAW_OFFSET {
+        Some(Command::Draw)
+    } else if index < ACTION_SIZE {
+        Some(Command::Take(all_expeditions()[index - TAKE_OFFSET]))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Game;
+    use brdgme_game::Gamer;
+
+    #[test]
+    fn layout_works() {
+        assert_eq!(72, CARD_SLOTS);
+        assert_eq!(371, OBSERVATION_SIZE);
+        assert_eq!(127, ACTION_SIZE);
+    }
+
+    #[test]
+    fn action_works() {
+        for i in 0..ACTION_SIZE {
+            let command = action(i).unwrap();
+            assert_eq!(Some(i), action_index(command));
+        }
+        assert_eq!(None, action(ACTION_SIZE));
+        assert_eq!(
+            None,
+            action_index(Command::Play((Expedition::Red, Value::N(11)).into()))
+        );
+        assert_eq!(
+            Some(DISCARD_OFFSET + 14),
+            action_index(Command::Discard((Expedition::Green, Value::N(5)).into()))
+        );
+    }
+
+    #[test]
+    fn encode_works() {
+        let mut game = Game::new_with_seed(2, 1).unwrap().0;
+        game.hands[0] = vec![
+            (Expedition::Red, Value::Investment).into(),
+            (Expedition::Red, Value::Investment).into(),
+            (Expedition::Blue, Value::N(10)).into(),
+        ];
+        game.expeditions[1] = vec![(Expedition::Green, Value::N(2)).into()];
+        game.discards = vec![
+            (Expedition::Yellow, Value::N(3)).into(),
+            (Expedition::Yellow, Value::N(7)).into(),
+        ];
+        let obs = encode(&game.player_state(0));
+        assert_eq!(OBSERVATION_SIZE, obs.len());
+        let hand = &obs[HAND_OFFSET..HAND_OFFSET + CARD_SLOTS];
+        assert_eq!(3.0, hand.iter().sum::<f32>());
+        assert_eq!(&[1.0, 1.0, 0.0][..], &hand[0..3]);
+        assert_eq!(1.0, hand[3 * SLOTS_PER_EXPEDITION + 11]);
+        // Player 1's tableau is second from player 0's perspective.
+        assert_eq!(1.0, obs[TABLEAU_OFFSET + CARD_SLOTS + SLOTS_PER_EXPEDITION + 3]);
+        assert_eq!(1.0, obs[DISCARD_TOP_OFFSET + 4 * SLOTS_PER_EXPEDITION + 8]);
+        assert_eq!(2.0 / 12.0, obs[DISCARD_SIZE_OFFSET + 4]);
+        assert_eq!(1.0, obs[PHASE_OFFSET]);
+        // From player 1's perspective their tableau is first.
+        let obs = encode(&game.player_state(1));
+        assert_eq!(1.0, obs[TABLEAU_OFFSET + SLOTS_PER_EXPEDITION + 3]);
+    }
+}
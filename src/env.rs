@@ -0,0 +1,243 @@
+use brdgme_game::errors::GameError;
+use brdgme_game::Gamer;
+
+use crate::bot::Bot;
+use crate::command::Command;
+use crate::encode::{self, ACTION_SIZE};
+use crate::options::GameOptions;
+use crate::Game;
+
+/// What the agent sees before choosing an action.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Observation {
+    /// The encoded player state, see the `encode` module for the layout.
+    pub features: Vec<f32>,
+    /// Which action indices are legal, all false once the game is finished.
+    pub legal_actions: Vec<bool>,
+}
+
+/// Extra details about a step.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Info {
+    pub round: usize,
+    /// Total scores for each player.
+    pub scores: Vec<isize>,
+    /// The commands opponents made after the agent's action.
+    pub opponent_commands: Vec<(usize, Command)>,
+}
+
+/// A reinforcement learning environment where an agent plays one seat and
+/// bots play the others.
+///
+/// The reward for a step is the change in the agent's score minus the best
+/// opponent's score, so it is only non zero when a round is scored.
+pub struct Env {
+    /// The seat the agent plays, takes effect on the next reset.
+    pub player: usize,
+    /// The seat the agent plays in the current game.
+    seat: usize,
+    options: GameOptions,
+    opponents: Vec<Box<dyn Bot>>,
+    game: Game,
+}
+
+impl Env {
+    /// Create an environment using the standard rules, with a bot for each
+    /// opponent.
+    pub fn new(players: usize, opponents: Vec<Box<dyn Bot>>) -> Result<Env, GameError> {
+        Env::with_options(players, GameOptions::for_players(players)?, opponents)
+    }
+
+    pub fn with_options(
+        players: usize,
+        options: GameOptions,
+        opponents: Vec<Box<dyn Bot>>,
+    ) -> Result<Env, GameError> {
+        if opponents.len() + 1 != players {
+            return Err(GameError::invalid_input(format!(
+                "expected {} opponent bots, got {}",
+                players.saturating_sub(1),
+                opponents.len()
+            )));
+        }
+        let game = Game::new_with_options(players, options.clone(), 0)?.0;
+        Ok(Env {
+            player: 0,
+            seat: 0,
+            options,
+            opponents,
+            game,
+        })
+    }
+
+    /// Start a new game from a seed, playing opponent turns until it's the
+    /// agent's turn. The current game is kept if an opponent fails.
+    pub fn reset(&mut self, seed: u64) -> Result<Observation, GameError> {
+        let mut game = Game::new_with_options(self.game.players, self.options.clone(), seed)?.0;
+        let seat = self.player % game.players;
+        self.play_opponents(&mut game, seat)?;
+        self.game = game;
+        self.seat = seat;
+        Ok(self.observation())
+    }
+
+    /// Make a move for the agent by action index, then play opponent turns
+    /// until it's the agent's turn again or the game is over. The game is left
+    /// unchanged if the action or an opponent's command fails.
+    pub fn step(&mut self, action: usize) -> Result<(Observation, f32, bool, Info), GameError> {
+        let command = encode::action(action)
+            .ok_or_else(|| GameError::invalid_input(format!("invalid action {}", action)))?;
+        let before = self.score_diff();
+        let mut game = self.game.clone();
+        match command {
+            Command::Play(c) => game.play(self.seat, c),
+            Command::Discard(c) => game.discard(self.seat, c),
+            Command::Draw => game.draw(self.seat),
+            Command::Take(e) => game.take(self.seat, e),
+        }?;
+        let opponent_commands = self.play_opponents(&mut game, self.seat)?;
+        self.game = game;
+        let reward = (self.score_diff() - before) as f32;
+        Ok((
+            self.observation(),
+            reward,
+            self.game.is_finished(),
+            Info {
+                round: self.game.round,
+                scores: (0..self.game.players)
+                    .map(|p| self.game.player_score(p))
+                    .collect(),
+                opponent_commands,
+            },
+        ))
+    }
+
+    /// Which action indices are legal for the agent.
+    pub fn legal_action_mask(&self) -> Vec<bool> {
+        let mut mask = vec![false; ACTION_SIZE];
+        for command in self.game.legal_moves(self.seat) {
+            if let Some(i) = encode::action_index(command) {
+                mask[i] = true;
+            }
+        }
+        mask
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    fn observation(&self) -> Observation {
+        Observation {
+            features: encode::encode(&self.game.player_state(self.seat)),
+            legal_actions: self.legal_action_mask(),
+        }
+    }
+
+    fn score_diff(&self) -> isize {
+        let best_opp = (0..self.game.players)
+            .filter(|&p| p != self.seat)
+            .map(|p| self.game.player_score(p))
+            .max()
+            .unwrap_or(0);
+        self.game.player_score(self.seat) - best_opp
+    }
+
+    fn play_opponents(
+        &mut self,
+        game: &mut Game,
+        seat: usize,
+    ) -> Result<Vec<(usize, Command)>, GameError> {
+        let mut commands = vec![];
+        while !game.is_finished() && game.current_player != seat {
+            let p = game.current_player;
+            // Opponent bots are stored in seat order skipping the agent.
+            let bot = if p < seat { p } else { p - 1 };
+            let command = self.opponents[bot].choose(&game.player_state(p));
+            game.apply_command(p, command)?;
+            commands.push((p, command));
+        }
+        Ok(commands)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::HeuristicBot;
+
+    /// Play an episode choosing the first legal action, drawing where possible
+    /// so the game can't stall.
+    fn run_episode(env: &mut Env, seed: u64) -> f32 {
+        let mut obs = env.reset(seed).unwrap();
+        let mut total = 0.0;
+        loop {
+            let draw = encode::action_index(Command::Draw).unwrap();
+            let action = if obs.legal_actions[draw] {
+                draw
+            } else {
+                obs.legal_actions.iter().position(|&l| l).unwrap()
+            };
+            let (next, reward, done, _) = env.step(action).unwrap();
+            total += reward;
+            if done {
+                assert!(next.legal_actions.iter().all(|&l| !l));
+                return total;
+            }
+            obs = next;
+        }
+    }
+
+    #[test]
+    fn env_works() {
+        for players in 2..4 {
+            let opponents: Vec<Box<dyn Bot>> = (1..players)
+                .map(|_| Box::new(HeuristicBot) as Box<dyn Bot>)
+                .collect();
+            let mut env = Env::new(players, opponents).unwrap();
+            for seed in 0..2 {
+                env.player = seed as usize;
+                let total = run_episode(&mut env, seed);
+                assert!(env.game().is_finished());
+                assert_eq!(env.score_diff() as f32, total);
+            }
+        }
+    }
+
+    #[test]
+    fn env_rejects_illegal_actions() {
+        let mut env = Env::new(2, vec![Box::new(HeuristicBot)]).unwrap();
+        let obs = env.reset(1).unwrap();
+        let illegal = obs.legal_actions.iter().position(|&l| !l).unwrap();
+        let before = env.game().clone();
+        assert!(env.step(illegal).is_err());
+        assert!(env.step(ACTION_SIZE).is_err());
+        assert_eq!(&before, env.game());
+        assert!(Env::new(3, vec![Box::new(HeuristicBot)]).is_err());
+    }
+
+    /// Always draws, which is invalid when it should play or discard.
+    struct DrawBot;
+
+    impl Bot for DrawBot {
+        fn choose(&mut self, _state: &crate::PlayerState) -> Command {
+            Command::Draw
+        }
+    }
+
+    #[test]
+    fn env_keeps_the_game_when_an_opponent_fails() {
+        let mut env = Env::new(2, vec![Box::new(DrawBot)]).unwrap();
+        let obs = env.reset(1).unwrap();
+        let before = env.game().clone();
+        let discard = obs.legal_actions.iter().rposition(|&l| l).unwrap();
+        let draw = encode::action_index(Command::Draw).unwrap();
+        env.step(discard).unwrap();
+        assert!(env.step(draw).is_err());
+        assert_ne!(&before, env.game());
+        assert_eq!(0, env.game().current_player);
+        env.player = 1;
+        assert!(env.reset(2).is_err());
+        assert_eq!(0, env.game().current_player);
+    }
+}
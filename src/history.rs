@@ -0,0 +1,213 @@
+use serde_derive::{Deserialize, Serialize};
+
+use brdgme_game::errors::GameError;
+use brdgme_game::Log;
+use brdgme_markup::Node as N;
+
+use std::cmp;
+
+use crate::card::Expedition;
+use crate::command::Command;
+use crate::{Game, Phase, Stats};
+
+/// A move made by a player, kept in the game history.
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct Move {
+    pub player: usize,
+    pub command: Command,
+    /// What's needed to reverse the move, draws reveal cards so can't be
+    /// undone.
+    undo: Option<Undo>,
+}
+
+impl Move {
+    pub fn can_undo(&self) -> bool {
+        self.undo.is_some()
+    }
+}
+
+/// The state a move changes which can't be worked out from the move itself.
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Undo {
+    phase: Phase,
+    current_player: usize,
+    discarded_expedition: Option<Expedition>,
+    stats: Stats,
+    /// Where the card came from, in the player's hand for plays and discards,
+    /// or in the discards for takes.
+    index: usize,
+    /// Whether a played or discarded card had been revealed to everyone.
+    #[serde(default)]
+    pub(crate) revealed: bool,
+}
+
+impl Game {
+    /// Capture the state needed to undo a move before it is made.
+    pub(crate) fn undo_point(&self, player: usize, index: usize) -> Undo {
+        Undo {
+            phase: self.phase,
+            current_player: self.current_player,
+            discarded_expedition: self.discarded_expedition,
+            stats: self.stats.get(player).cloned().unwrap_or_default(),
+            index,
+            revealed: false,
+        }
+    }
+
+    /// Whether the last move in the history can be undone.
+    pub(crate) fn last_move_can_undo(&self) -> bool {
+        matches!(self.history.last(), Some(m) if m.can_undo())
+    }
+
+    pub(crate) fn record_move(&mut self, player: usize, command: Command, undo: Option<Undo>) {
+        self.history.push(Move {
+            player,
+            command,
+            undo,
+        });
+    }
+
+    /// Move the finished round's commands out of the history.
+    pub(crate) fn finish_round_history(&mut self) {
+        let commands = self.history.drain(..).map(|m| (m.player, m.command)).collect();
+        self.past_rounds.push(commands);
+    }
+
+    /// The commands made in each round so far, undone moves are left out.
+    pub fn round_commands(&self) -> Vec<Vec<(usize, Command)>> {
+        let mut rounds = self.past_rounds.clone();
+        if !self.history.is_empty() {
+            rounds.push(self.history.iter().map(|m| (m.player, m.command)).collect());
+        }
+        rounds
+    }
+
+    /// Undo the last move if it was made by the player. Draws reveal cards so
+    /// can't be undone, which also stops undoing back past them.
+    pub fn undo(&mut self, player: usize) -> Result<Vec<Log>, GameError> {
+        let (command, undo) = match self.history.last() {
+            Some(&Move {
+                player: p,
+                command,
+                undo: Some(ref undo),
+            }) if p == player =>
+            {
+                (command, undo.clone())
+            }
+            Some(&Move {
+                player: p,
+                undo: None,
+                ..
+            }) if p == player =>
+            {
+                return Err(GameError::invalid_input(
+                    "you can't undo that as it revealed a card",
+                ))
+            }
+            _ => return Err(GameError::invalid_input("you have nothing to undo")),
+        };
+        let c = match command {
+            Command::Play(_) => self.expeditions.get_mut(player).and_then(|e| e.pop()),
+            Command::Discard(_) => self.discards.pop(),
+            Command::Take(_) => self.hands.get_mut(player).and_then(|h| h.pop()),
+            Command::Draw => None,
+        }.ok_or_else(|| GameError::internal("could not find the card to undo"))?;
+        if let Some(r) = self.revealed.get_mut(player) {
+            match command {
+                Command::Take(_) => {
+                    // Undoing a discard can reorder the revealed cards, so
+                    // remove the taken card itself rather than the last.
+                    if let Some(i) = r.iter().rposition(|&rc| rc == c) {
+                        r.remove(i);
+                    }
+                }
+                _ if undo.revealed => r.push(c),
+                _ => {}
+            }
+        }
+        match command {
+            Command::Play(_) | Command::Discard(_) => {
+                let hand = self.hands
+                    .get_mut(player)
+                    .ok_or_else(|| GameError::internal("could not find player hand"))?;
+                let index = cmp::min(undo.index, hand.len());
+                hand.insert(index, c);
+            }
+            _ => {
+                let index = cmp::min(undo.index, self.discards.len());
+                self.discards.insert(index, c);
+            }
+        }
+        self.phase = undo.phase;
+        self.current_player = undo.current_player;
+        self.discarded_expedition = undo.discarded_expedition;
+        if let Some(s) = self.stats.get_mut(player) {
+            *s = undo.stats;
+        }
+        self.history.pop();
+        Ok(vec![Log::public(vec![
+            N::Player(player),
+            N::text(" undid "),
+            N::Bold(vec![N::text(command.to_string())]),
+        ])])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::Value;
+
+    #[test]
+    fn undo_works() {
+        let mut game = Game::new_with_seed(2, 1).unwrap().0;
+        game.hands[0][0] = (Expedition::Red, Value::N(5)).into();
+        game.hands[1][0] = (Expedition::Blue, Value::N(5)).into();
+        let start = game.clone();
+        game.play(0, (Expedition::Red, Value::N(5)).into()).unwrap();
+        game.undo(0).unwrap();
+        assert_eq!(start, game);
+        game.discard(0, (Expedition::Red, Value::N(5)).into())
+            .unwrap();
+        game.undo(0).unwrap();
+        assert_eq!(start, game);
+
+        game.discard(0, (Expedition::Red, Value::N(5)).into())
+            .unwrap();
+        game.draw(0).unwrap();
+        // Draws can't be undone.
+        assert!(game.undo(0).is_err());
+        assert!(game.undo(1).is_err());
+        game.discard(1, (Expedition::Blue, Value::N(5)).into())
+            .unwrap();
+        let before_take = game.clone();
+        game.take(1, Expedition::Red).unwrap();
+        // Only the player who made the move can undo it.
+        assert!(game.undo(0).is_err());
+        game.undo(1).unwrap();
+        assert_eq!(before_take, game);
+        game.undo(1).unwrap();
+        assert!(game.undo(1).is_err());
+        assert_eq!(Phase::PlayOrDiscard, game.phase);
+        assert_eq!(1, game.current_player);
+        assert_eq!(start.stats[1], game.stats[1]);
+    }
+
+    #[test]
+    fn history_clears_at_round_end() {
+        let mut game = Game::new_with_seed(2, 1).unwrap().0;
+        let round = game.round;
+        let mut commands = 0;
+        while game.round == round {
+            let p = game.current_player;
+            let c = game.hands[p][0];
+            game.discard(p, c).unwrap();
+            game.draw(p).unwrap();
+            commands += 2;
+        }
+        assert!(game.history.is_empty());
+        assert_eq!(1, game.past_rounds.len());
+        assert_eq!(commands, game.past_rounds[0].len());
+        assert!(game.undo(game.current_player).is_err());
+    }
+}
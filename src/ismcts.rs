@@ -0,0 +1,408 @@
+use rand::isaac::Isaac64Rng;
+use rand::{thread_rng, Rng, SeedableRng};
+
+use brdgme_game::Gamer;
+
+use std::cmp::{self, Reverse};
+use std::time::{Duration, Instant};
+
+use crate::bot::{Bot, HeuristicBot};
+use crate::card::{Card, Expedition, Value};
+use crate::command::Command;
+use crate::solver::known_node;
+use crate::{Game, Phase, PlayerState};
+
+const DEFAULT_EXPLORATION: f64 = 0.4;
+/// The biggest jump in value the rollout policy will play to an expedition.
+const MAX_ROLLOUT_GAP: usize = 3;
+/// Most card value the rollout policy expects to draw for a new expedition.
+const MAX_ROLLOUT_OPTIMISM: usize = 8;
+/// Round score difference which maps to a reward of roughly 0.73, used to
+/// squash score differences into the 0 to 1 range.
+const REWARD_SCALE: f64 = 20.0;
+
+/// How long a search is allowed to run for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Budget {
+    Iterations(usize),
+    Time(Duration),
+}
+
+/// The statistics for a move at the root of the search tree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MoveStats {
+    pub command: Command,
+    pub visits: usize,
+    pub mean_reward: f64,
+}
+
+struct Node {
+    command: Option<Command>,
+    player: usize,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    visits: f64,
+    reward: f64,
+    availability: f64,
+}
+
+impl Node {
+    fn new(command: Option<Command>, player: usize, parent: Option<usize>) -> Node {
+        Node {
+            command,
+            player,
+            parent,
+            children: vec![],
+            visits: 0.0,
+            reward: 0.0,
+            availability: 1.0,
+        }
+    }
+
+    fn ucb(&self, exploration: f64) -> f64 {
+        self.reward / self.visits + exploration * (self.availability.ln() / self.visits).sqrt()
+    }
+}
+
+/// A single observer information set Monte Carlo tree search bot.
+///
+/// Every iteration deals the cards the player can't see at random, searches the
+/// tree using only moves legal in that deal, then plays the rest of the round
+/// out with a fast greedy policy. Rewards are based on the score difference at
+/// the end of the current round.
+pub struct IsmctsBot {
+    pub budget: Budget,
+    pub exploration: f64,
+    rng: Isaac64Rng,
+}
+
+impl IsmctsBot {
+    pub fn new(budget: Budget) -> IsmctsBot {
+        IsmctsBot::with_seed(budget, thread_rng().gen())
+    }
+
+    /// Create a bot which will make the same choices for the same states.
+    pub fn with_seed(budget: Budget, seed: u64) -> IsmctsBot {
+        IsmctsBot {
+            budget,
+            exploration: DEFAULT_EXPLORATION,
+            rng: Isaac64Rng::from_seed(&[seed][..]),
+        }
+    }
+
+    /// Search from the state and return statistics for every move tried from
+    /// the root, most visited first.
+    pub fn search(&mut self, state: &PlayerState) -> Vec<MoveStats> {
+        let mut nodes = vec![Node::new(None, state.player, None)];
+        let started = Instant::now();
+        let mut iterations = 0;
+        loop {
+            match self.budget {
+                Budget::Iterations(n) if iterations >= n => break,
+                Budget::Time(d) if iterations > 0 && started.elapsed() >= d => break,
+                _ => {}
+            }
+            self.iterate(state, &mut nodes);
+            iterations += 1;
+        }
+        let mut stats: Vec<MoveStats> = nodes[0]
+            .children
+            .iter()
+            .filter_map(|&c| {
+                let n = &nodes[c];
+                n.command.map(|command| MoveStats {
+                    command,
+                    visits: n.visits as usize,
+                    mean_reward: n.reward / n.visits,
+                })
+            })
+            .collect();
+        stats.sort_by_key(|s| Reverse(s.visits));
+        stats
+    }
+
+    fn iterate(&mut self, state: &PlayerState, nodes: &mut Vec<Node>) {
+        let mut game = determinize(state, &mut self.rng);
+        let round = game.round;
+        let mut node = 0;
+        // Selection and expansion.
+        while !game.is_finished() && game.round == round {
+            let player = game.current_player;
+            let moves = game.legal_moves(player);
+            let untried: Vec<Command> = moves
+                .iter()
+                .filter(|m| {
+                    !nodes[node]
+                        .children
+                        .iter()
+                        .any(|&c| nodes[c].command == Some(**m))
+                })
+                .cloned()
+                .collect();
+            if let Some(&m) = self.rng.choose(&untried) {
+                if game.apply_command(player, m).is_err() {
+                    return;
+                }
+                let child = nodes.len();
+                nodes.push(Node::new(Some(m), player, Some(node)));
+                nodes[node].children.push(child);
+                node = child;
+                break;
+            }
+            let mut best: Option<(f64, usize)> = None;
+            for &c in &nodes[node].children.clone() {
+                if !moves.iter().any(|m| nodes[c].command == Some(*m)) {
+                    continue;
+                }
+                nodes[c].availability += 1.0;
+                let ucb = nodes[c].ucb(self.exploration);
+                if best.map(|(b, _)| ucb > b).unwrap_or(true) {
+                    best = Some((ucb, c));
+                }
+            }
+            let child = match best {
+                Some((_, c)) => c,
+                None => break,
+            };
+            if let Some(m) = nodes[child].command {
+                if game.apply_command(player, m).is_err() {
+                    return;
+                }
+            }
+            node = child;
+        }
+        // Simulation.
+        while !game.is_finished() && game.round == round {
+            let player = game.current_player;
+            let m = rollout_move(&game, player);
+            if game.apply_command(player, m).is_err() {
+                return;
+            }
+        }
+        let rewards = round_rewards(&game);
+        // Backpropagation.
+        let mut current = Some(node);
+        while let Some(n) = current {
+            nodes[n].visits += 1.0;
+            nodes[n].reward += rewards.get(nodes[n].player).cloned().unwrap_or(0.0);
+            current = nodes[n].parent;
+        }
+    }
+}
+
+impl Bot for IsmctsBot {
+    fn choose(&mut self, state: &PlayerState) -> Command {
+        match self.search(state).first() {
+            Some(s) => s.command,
+            None => HeuristicBot.choose(state),
+        }
+    }
+}
+
+/// A cheap greedy policy for playing out rounds: continue expeditions with the
+/// smallest gap, open expeditions the hand can nearly pay for, otherwise
+/// discard a card we can't use, and only take discards which slot straight in.
+fn rollout_move(game: &Game, player: usize) -> Command {
+    let hand = &game.hands[player];
+    let expedition = &game.expeditions[player];
+    let top = |e: Expedition| -> Option<usize> {
+        expedition
+            .iter()
+            .filter(|c| c.expedition == e)
+            .map(|c| match c.value {
+                Value::N(n) => n,
+                Value::Investment => game.options.min_value.saturating_sub(1),
+            })
+            .max()
+    };
+    let gap = |c: Card| -> usize {
+        match (top(c.expedition), c.value) {
+            (Some(t), Value::N(n)) => n.saturating_sub(t + 1),
+            (None, Value::N(n)) => n.saturating_sub(game.options.min_value),
+            (_, Value::Investment) => 0,
+        }
+    };
+    if game.phase == Phase::DrawOrTake {
+        for e in game.options.expeditions() {
+            if game.discarded_expedition == Some(e) || top(e).is_none() {
+                continue;
+            }
+            if let Some(c) = game.available_discard(e) {
+                if game.can_play(player, c) && gap(c) <= 1 {
+                    return Command::Take(e);
+                }
+            }
+        }
+        return Command::Draw;
+    }
+    let mut play: Option<(usize, Card)> = None;
+    for &c in hand {
+        if !game.can_play(player, c) {
+            continue;
+        }
+        if top(c.expedition).is_none() {
+            let hand_sum: usize = hand.iter()
+                .filter(|hc| hc.expedition == c.expedition)
+                .map(|hc| match hc.value {
+                    Value::N(n) => n,
+                    Value::Investment => 0,
+                })
+                .sum();
+            // Expect a few more cards to turn up, but not too many.
+            let optimism = cmp::min(game.deck.len() / game.players, MAX_ROLLOUT_OPTIMISM);
+            if ((hand_sum + optimism) as isize) < game.options.expedition_cost {
+                continue;
+            }
+        }
+        let g = gap(c);
+        if g <= MAX_ROLLOUT_GAP && play.map(|(pg, pc)| (g, c) < (pg, pc)).unwrap_or(true) {
+            play = Some((g, c));
+        }
+    }
+    if let Some((_, c)) = play {
+        return Command::Play(c);
+    }
+    // Prefer discarding cards we can no longer play, then cards for
+    // expeditions we haven't started, then cards opponents can't use, then low
+    // cards.
+    let feeds = |c: Card| -> bool {
+        (0..game.players).any(|p| {
+            p != player && game.can_play(p, c)
+                && game.expeditions[p].iter().any(|ec| ec.expedition == c.expedition)
+        })
+    };
+    hand.iter()
+        .min_by_key(|&&c| {
+            (
+                game.can_play(player, c),
+                top(c.expedition).is_some(),
+                feeds(c),
+                c.value,
+            )
+        })
+        .map(|&c| Command::Discard(c))
+        .unwrap_or(Command::Draw)
+}
+
+/// Rewards for each player from the difference between their score for the
+/// last round and the best of their opponents.
+fn round_rewards(game: &Game) -> Vec<f64> {
+    let last: Vec<isize> = (0..game.players)
+        .map(|p| {
+            game.scores
+                .get(p)
+                .and_then(|s| s.last())
+                .cloned()
+                .unwrap_or(0)
+        })
+        .collect();
+    (0..game.players)
+        .map(|p| {
+            let best_opp = (0..game.players)
+                .filter(|&o| o != p)
+                .map(|o| last[o])
+                .max()
+                .unwrap_or(0);
+            1.0 / (1.0 + (-((last[p] - best_opp) as f64) / REWARD_SCALE).exp())
+        })
+        .collect()
+}
+
+/// Build a full game consistent with what the player can see, dealing the
+/// cards they can't see at random.
+pub fn determinize<R: Rng>(state: &PlayerState, rng: &mut R) -> Game {
+    let public = &state.public;
+    let known = known_node(state);
+    let (mut game, mut buried) = (known.game, known.buried);
+    // Buried discards go under the top of their piles in a random order.
+    rng.shuffle(buried.as_mut_slice());
+    buried.append(&mut game.discards);
+    game.discards = buried;
+
+    let mut hidden: Vec<Card> = public
+        .options
+        .expeditions()
+        .iter()
+        .flat_map(|e| state.unseen.get(e).cloned().unwrap_or_default())
+        .collect();
+    rng.shuffle(hidden.as_mut_slice());
+    // The rival's pile is dealt from the bottom of the hidden cards.
+    if let (Some(rival), Some(r)) = (game.rival.as_mut(), public.rival.as_ref()) {
+        rival.pile = hidden.split_off(hidden.len() - r.pile_remaining.min(hidden.len()));
+    }
+    if public.hands.is_none() {
+        // Cards taken from the discards are already known to be in the hand.
+        for (p, hand) in game.hands.iter_mut().enumerate() {
+            if p != state.player {
+                let n = public
+                    .options
+                    .hand_size
+                    .saturating_sub(hand.len())
+                    .min(hidden.len());
+                hand.extend(hidden.drain(..n));
+            }
+        }
+    }
+    if public.deck.is_none() {
+        game.deck = hidden;
+    }
+    game.seed = rng.gen();
+    game
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn determinize_works() {
+        let mut game = Game::new_with_seed(2, 1).unwrap().0;
+        let c = game.hands[0][0];
+        game.discard(0, c).unwrap();
+        let state = game.player_state(0);
+        let mut rng = Isaac64Rng::from_seed(&[1][..]);
+        let d = determinize(&state, &mut rng);
+        assert_eq!(game.hands[0], d.hands[0]);
+        assert_eq!(game.hands[1].len(), d.hands[1].len());
+        assert_eq!(game.deck.len(), d.deck.len());
+        assert_eq!(game.discards, d.discards);
+        assert_eq!(game.phase, d.phase);
+    }
+
+    #[test]
+    fn determinize_keeps_seen_discards() {
+        let mut game = Game::new_with_seed(2, 1).unwrap().0;
+        // Bury a red card under another, both seen as they were discarded.
+        let mut red = game
+            .deck
+            .iter()
+            .cloned()
+            .filter(|c| c.expedition == Expedition::Red && c.value != Value::Investment)
+            .take(2);
+        let (buried, top) = (red.next().unwrap(), red.next().unwrap());
+        game.deck.retain(|&c| c != buried && c != top);
+        game.discards = vec![buried, top];
+        let state = game.player_state(0);
+        for seed in 0..10u64 {
+            let mut rng = Isaac64Rng::from_seed(&[seed][..]);
+            let d = determinize(&state, &mut rng);
+            assert_eq!(game.discards, d.discards);
+            assert!(!d.hands[1].contains(&buried));
+            assert!(!d.deck.contains(&buried));
+            assert_eq!(game.hands[1].len(), d.hands[1].len());
+            assert_eq!(game.deck.len(), d.deck.len());
+        }
+    }
+
+    #[test]
+    fn ismcts_bot_chooses_legal_moves() {
+        let mut game = Game::new_with_seed(2, 1).unwrap().0;
+        let mut bot = IsmctsBot::with_seed(Budget::Iterations(20), 1);
+        for _ in 0..4 {
+            let p = game.current_player;
+            let m = bot.choose(&game.player_state(p));
+            assert!(game.legal_moves(p).contains(&m));
+            game.apply_command(p, m).unwrap();
+        }
+    }
+}
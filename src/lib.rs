@@ -1,11 +1,31 @@
+pub mod bot;
 pub mod card;
-mod command;
+pub mod command;
+pub mod encode;
+pub mod env;
+pub mod history;
+pub mod ismcts;
+pub mod notation;
+pub mod options;
+pub mod position;
+pub mod puzzle;
+#[cfg(test)]
+mod properties;
+pub mod replay;
+pub mod score;
+pub mod solo;
+pub mod solver;
+#[cfg(test)]
+mod test_helpers;
+pub mod validate;
 mod render;
 
+use serde::de;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_derive::{Serialize, Deserialize};
-use rand::{thread_rng, Rng};
+use rand::isaac::Isaac64Rng;
+use rand::{thread_rng, Rng, SeedableRng};
 
-use brdgme_game::command::parser::Output as ParseOutput;
 use brdgme_game::command::Spec as CommandSpec;
 use brdgme_game::errors::GameError;
 use brdgme_game::game::gen_placings;
@@ -15,13 +35,19 @@ use brdgme_markup::Node as N;
 use std::collections::{HashMap, HashSet};
 use std::default::Default;
 
-use crate::card::{expeditions, Card, Expedition, Value};
-use crate::command::Command;
+use crate::card::{Card, Expedition, Value};
+use crate::command::{Command, Input};
+use crate::history::Move;
+use crate::options::GameOptions;
+use crate::position::Position;
+use crate::score::ScoreBreakdown;
+use crate::solo::{Rival, RivalState};
 
 const INVESTMENTS: usize = 3;
 pub const ROUNDS: usize = 3;
 pub const START_ROUND: usize = 1;
-const MIN_PLAYERS: usize = 2;
+/// A single player plays solo against an automated rival.
+const MIN_PLAYERS: usize = 1;
 const MAX_PLAYERS: usize = 3;
 const MIN_VALUE: usize = 2;
 const MAX_VALUE: usize = 10;
@@ -29,8 +55,8 @@ const HAND_SIZE_2P: usize = 8;
 const HAND_SIZE_3P: usize = 7;
 const EXP_COST_2P: isize = 20;
 const EXP_COST_3P: isize = 15;
-const EXP_BONUS_SIZE_2P: isize = 8;
-const EXP_BONUS_SIZE_3P: isize = 7;
+const EXP_BONUS_SIZE_2P: usize = 8;
+const EXP_BONUS_SIZE_3P: usize = 7;
 
 #[derive(PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
 pub enum Phase {
@@ -52,10 +78,15 @@ pub struct Stats {
     pub draws: usize,
     pub turns: usize,
     pub investments: usize,
+    /// Rounds in which the player started at least one expedition.
     pub expeditions: usize,
+    /// Expeditions started across every round.
+    #[serde(default)]
+    pub expeditions_opened: usize,
 }
 
 #[derive(Default, PartialEq, Debug, Clone, Serialize, Deserialize)]
+#[serde(remote = "Self")]
 pub struct Game {
     pub players: usize,
     pub round: usize,
@@ -68,6 +99,57 @@ pub struct Game {
     pub current_player: usize,
     pub discarded_expedition: Option<Expedition>,
     pub stats: Vec<Stats>,
+    #[serde(default)]
+    pub seed: u64,
+    #[serde(default = "missing_options")]
+    pub options: GameOptions,
+    /// Moves made this round, cleared when the round ends as undo can't reach
+    /// back past a draw.
+    #[serde(default)]
+    pub history: Vec<Move>,
+    /// The commands of each finished round, kept for replays.
+    #[serde(default)]
+    pub past_rounds: Vec<Vec<(usize, Command)>>,
+    /// Cards in each hand which everyone has seen as they were taken from the
+    /// discards.
+    #[serde(default)]
+    pub revealed: Vec<Vec<Card>>,
+    /// Only set in solo games.
+    #[serde(default)]
+    pub rival: Option<Rival>,
+    /// The position the game was built from rather than dealt from its seed,
+    /// replays start from here.
+    #[serde(default)]
+    pub start: Option<Position>,
+}
+
+/// Stands in for the options of games saved before options were added, until
+/// they're filled in from the number of players. Valid options always have at
+/// least one round.
+fn missing_options() -> GameOptions {
+    GameOptions {
+        rounds: 0,
+        ..GameOptions::default()
+    }
+}
+
+impl Serialize for Game {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Game::serialize(self, serializer)
+    }
+}
+
+/// Games saved before a field was added load with its default, and games
+/// saved before options were added use the rules for the number of players.
+impl<'de> Deserialize<'de> for Game {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Game, D::Error> {
+        let mut game = Game::deserialize(deserializer)?;
+        if game.options == missing_options() {
+            game.options = GameOptions::for_players(game.players)
+                .map_err(|e| de::Error::custom(e.to_string()))?;
+        }
+        Ok(game)
+    }
 }
 
 #[derive(Default, Serialize, Deserialize)]
@@ -78,9 +160,25 @@ pub struct PubState {
     pub phase: Phase,
     pub deck_remaining: usize,
     pub discards: HashMap<Expedition, Value>,
+    /// The number of cards in each discard pile.
+    pub discard_sizes: HashMap<Expedition, usize>,
+    /// The full discard piles from bottom to top, only public when playing
+    /// with open discards.
+    pub discard_piles: Option<HashMap<Expedition, Vec<Card>>>,
     pub scores: Vec<Vec<isize>>,
     pub expeditions: Vec<Vec<Card>>,
     pub current_player: usize,
+    pub discarded_expedition: Option<Expedition>,
+    pub options: GameOptions,
+    /// Score breakdowns for each player's current expeditions.
+    pub score_breakdowns: Vec<Vec<ScoreBreakdown>>,
+    /// Cards in each hand which everyone saw being taken from the discards.
+    pub revealed: Vec<Vec<Card>>,
+    /// Every hand, only public when playing with open information.
+    pub hands: Option<Vec<Vec<Card>>>,
+    /// The deck from the top, only public when playing with open information.
+    pub deck: Option<Vec<Card>>,
+    pub rival: Option<RivalState>,
 }
 
 #[derive(Default, Serialize, Deserialize)]
@@ -88,22 +186,64 @@ pub struct PlayerState {
     pub public: PubState,
     pub player: usize,
     pub hand: Vec<Card>,
+    /// Cards the player hasn't seen this round, either in the deck or in
+    /// opponent hands.
+    pub unseen: HashMap<Expedition, Vec<Card>>,
 }
 
-fn initial_deck() -> Vec<Card> {
-    let mut deck: Vec<Card> = vec![];
-    for e in card::expeditions() {
-        for _ in 0..INVESTMENTS {
-            deck.push((e, Value::Investment).into());
+impl Game {
+    /// Create a new game using a fixed seed, every round will be shuffled the
+    /// same way for the same seed.
+    pub fn new_with_seed(players: usize, seed: u64) -> Result<(Self, Vec<Log>), GameError> {
+        Game::new_with_options(players, GameOptions::for_players(players)?, seed)
+    }
+
+    /// Create a new game with custom rules.
+    pub fn new_with_options(
+        players: usize,
+        options: GameOptions,
+        seed: u64,
+    ) -> Result<(Self, Vec<Log>), GameError> {
+        if players < MIN_PLAYERS || players > MAX_PLAYERS {
+            return Err(GameError::PlayerCount {
+                min: MIN_PLAYERS,
+                max: MAX_PLAYERS,
+                given: players,
+            });
         }
-        for v in MIN_VALUE..MAX_VALUE + 1 {
-            deck.push((e, Value::N(v)).into());
+        options.validate(players)?;
+        let mut stats = vec![];
+        let mut scores = vec![];
+        for _ in 0..players {
+            stats.push(Stats::default());
+            scores.push(vec![]);
         }
+        let mut g = Game {
+            players,
+            round: START_ROUND,
+            stats,
+            scores,
+            seed,
+            options,
+            rival: if players == 1 {
+                Some(Rival::default())
+            } else {
+                None
+            },
+            ..Game::default()
+        };
+        let logs = g.start_round()?;
+        Ok((g, logs))
+    }
+
+    /// The RNG for the current round, derived from the game seed and the round
+    /// number so it can be recreated from serialised state. A named generator
+    /// is seeded with fixed width words so deals are the same on every
+    /// platform.
+    fn round_rng(&self) -> Isaac64Rng {
+        Isaac64Rng::from_seed(&[self.seed, self.round as u64][..])
     }
-    deck
-}
 
-impl Game {
     fn leaders(&self) -> HashSet<usize> {
         let mut lead: HashSet<usize> = HashSet::new();
         let mut highest: isize = std::isize::MIN;
@@ -126,19 +266,22 @@ impl Game {
             self.round
         ))])];
         // Grab a new deck and shuffle it.
-        let mut deck = initial_deck();
-        thread_rng().shuffle(deck.as_mut_slice());
+        let mut deck = self.options.initial_deck();
+        self.round_rng().shuffle(deck.as_mut_slice());
         self.deck = deck;
         // Clear out discards, hands and expeditions.
         self.discards = vec![];
         self.hands = vec![];
         self.expeditions = vec![];
+        self.revealed = vec![];
         // Initialise player hands and expedition and draw initial cards.
         for p in 0..self.players {
             self.hands.push(vec![]);
             self.expeditions.push(vec![]);
+            self.revealed.push(vec![]);
             logs.extend(self.draw_hand_full(p)?);
         }
+        self.deal_rival();
         if self.round > START_ROUND {
             let leaders = self.leaders();
             loop {
@@ -158,7 +301,7 @@ impl Game {
         for p in 0..self.players {
             let mut round_score: isize = 0;
             if let Some(p_exp) = self.expeditions.get(p) {
-                round_score = score(self.players, p_exp);
+                round_score = self.options.score(p_exp);
             }
             self.scores.get_mut(p).map(|s| s.push(round_score));
             logs.push(Log::public(vec![
@@ -168,8 +311,24 @@ impl Game {
                 N::text(" points, now on "),
                 N::Bold(vec![N::text(format!("{}", self.player_score(p)))]),
             ]));
+            if let Some(p_exp) = self.expeditions.get(p) {
+                for b in self.options.score_breakdown(p_exp) {
+                    logs.push(Log::public(vec![
+                        N::Player(p),
+                        N::text(" "),
+                        N::Bold(vec![
+                            N::Fg(
+                                b.expedition.color().into(),
+                                vec![N::text(b.expedition.name())],
+                            ),
+                        ]),
+                        N::text(format!(": {}", b)),
+                    ]));
+                }
+            }
         }
-        if self.round < START_ROUND + ROUNDS {
+        logs.extend(self.score_rival());
+        if self.round < START_ROUND + self.options.rounds {
             self.start_round().map(|l| {
                 logs.extend(l);
                 logs
@@ -181,7 +340,18 @@ impl Game {
     }
 
     fn game_over_log(&self) -> Log {
-        Log::public(vec![N::Bold(vec![N::text("The game is over.")])])
+        let mut content = vec![N::Bold(vec![N::text("The game is over.")])];
+        if let Some(ref rival) = self.rival {
+            let diff = self.player_score(0) - rival.score();
+            content.push(N::text(if diff > 0 {
+                format!(" You beat the rival by {} points.", diff)
+            } else if diff < 0 {
+                format!(" The rival beat you by {} points.", -diff)
+            } else {
+                " You tied with the rival.".to_string()
+            }));
+        }
+        Log::public(content)
     }
 
     fn assert_phase(&self, phase: Phase) -> Result<(), GameError> {
@@ -197,14 +367,19 @@ impl Game {
         self.assert_player_turn(player)?;
         self.assert_phase(Phase::DrawOrTake)?;
         let r = self.round;
-        let logs = self.draw_hand_full(player)?;
+        let mut logs = self.draw_hand_full(player)?;
         if r == self.round {
             // Only run next phase if a new round wasn't started, if a new round
             // was started then everything will already be initialised.
             self.next_phase();
+            logs.extend(self.rival_turn());
         }
         self.stats[player].draws += 1;
         self.stats[player].turns += 1;
+        self.record_move(player, Command::Draw, None);
+        if r != self.round {
+            self.finish_round_history();
+        }
         Ok(logs)
     }
 
@@ -249,19 +424,27 @@ impl Game {
             let c = *self.discards
                 .get(index)
                 .ok_or_else(|| GameError::internal("could not find discard card"))?;
+            let undo = self.undo_point(player, index);
             self.hands
                 .get_mut(player)
                 .ok_or_else(|| GameError::internal("could not find player hand"))?
                 .push(c);
             self.discards.remove(index);
+            self.reveal(player, c);
             self.next_phase();
             self.stats[player].takes += 1;
             self.stats[player].turns += 1;
-            Ok(vec![Log::public(vec![
+            let mut logs = vec![Log::public(vec![
                 N::Player(player),
                 N::text(" took "),
                 render::card(&c),
-            ])])
+            ])];
+            // The rival flipping a card can't be undone.
+            let rival_logs = self.rival_turn();
+            let undo = if rival_logs.is_empty() { Some(undo) } else { None };
+            logs.extend(rival_logs);
+            self.record_move(player, Command::Take(expedition), undo);
+            Ok(logs)
         } else {
             Err(GameError::invalid_input(
                 "there are no discarded cards for that expedition",
@@ -297,11 +480,14 @@ impl Game {
         self.assert_not_finished()?;
         self.assert_player_turn(player)?;
         self.assert_phase(Phase::PlayOrDiscard)?;
+        let mut undo = self.undo_point(player, self.hand_index(player, c)?);
         self.remove_player_card(player, c)?;
+        undo.revealed = self.unreveal(player, c);
         self.discards.push(c);
         self.discarded_expedition = Some(c.expedition);
         self.next_phase();
         self.stats[player].discards += 1;
+        self.record_move(player, Command::Discard(c), Some(undo));
         Ok(vec![Log::public(vec![
             N::Player(player),
             N::text(" discarded "),
@@ -310,6 +496,11 @@ impl Game {
     }
 
     fn assert_has_card(&self, player: usize, c: Card) -> Result<(), GameError> {
+        self.hand_index(player, c)?;
+        Ok(())
+    }
+
+    fn hand_index(&self, player: usize, c: Card) -> Result<usize, GameError> {
         self.hands
             .get(player)
             .ok_or_else(|| {
@@ -319,17 +510,79 @@ impl Game {
                 h.iter()
                     .position(|hc| c == *hc)
                     .ok_or_else(|| GameError::invalid_input(format!("you don't have {}", c)))
-            })?;
-        Ok(())
+            })
+    }
+
+    /// Cards the player hasn't seen this round, which are the cards not in
+    /// their hand, the expeditions, the discards or revealed in other hands.
+    /// Everything is seen when playing with open information.
+    pub fn unseen_cards(&self, player: usize) -> Vec<Card> {
+        if self.options.open_information {
+            return vec![];
+        }
+        let mut unseen = self.options.initial_deck();
+        let seen = self.hands
+            .get(player)
+            .into_iter()
+            .chain(self.expeditions.iter())
+            .chain(self.rival.iter().map(|r| &r.expeditions))
+            .chain(
+                self.revealed
+                    .iter()
+                    .enumerate()
+                    .filter(|&(p, _)| p != player)
+                    .map(|(_, r)| r),
+            )
+            .flat_map(|cards| cards.iter())
+            .chain(self.discards.iter());
+        for c in seen {
+            if let Some(i) = unseen.iter().position(|uc| uc == c) {
+                unseen.remove(i);
+            }
+        }
+        unseen
+    }
+
+    /// Mark a card taken from the discards as seen by everyone.
+    fn reveal(&mut self, player: usize, c: Card) {
+        if self.revealed.len() < self.players {
+            self.revealed.resize(self.players, vec![]);
+        }
+        self.revealed[player].push(c);
     }
 
-    fn highest_value_in_expedition(&self, player: usize, expedition: Expedition) -> Option<usize> {
-        self.expeditions.get(player).and_then(|e| {
-            e.iter()
-                .filter(|&c| c.expedition == expedition && c.value != Value::Investment)
-                .map(|&c| if let Value::N(n) = c.value { n } else { 0 })
-                .max()
-        })
+    /// Remove a card leaving a player's hand from their revealed cards,
+    /// returning whether it was revealed.
+    fn unreveal(&mut self, player: usize, c: Card) -> bool {
+        match self.revealed.get_mut(player) {
+            Some(r) => match r.iter().position(|&rc| rc == c) {
+                Some(i) => {
+                    r.remove(i);
+                    true
+                }
+                None => false,
+            },
+            None => false,
+        }
+    }
+
+    /// Suggest a move to the player privately, without changing the game.
+    pub fn hint(&self, player: usize) -> Result<Vec<Log>, GameError> {
+        self.assert_not_finished()?;
+        self.assert_player_turn(player)?;
+        let (command, reason) = bot::hint(&self.player_state(player));
+        Ok(vec![Log::private(
+            vec![
+                N::text("Hint: "),
+                N::Bold(vec![N::text(command.to_string())]),
+                N::text(format!(", {}", reason)),
+            ],
+            vec![player],
+        )])
+    }
+
+    fn can_play(&self, player: usize, c: Card) -> bool {
+        card::can_play(self.expeditions.get(player).map_or(&[], Vec::as_slice), c)
     }
 
     pub fn play(&mut self, player: usize, c: Card) -> Result<Vec<Log>, GameError> {
@@ -337,35 +590,27 @@ impl Game {
         self.assert_player_turn(player)?;
         self.assert_phase(Phase::PlayOrDiscard)?;
         self.assert_has_card(player, c)?;
-        if let Some(hn) = self.highest_value_in_expedition(player, c.expedition) {
-            match c.value {
-                Value::Investment => {
-                    return Err(GameError::invalid_input(format!(
-                        "you can't play {} as you've already played a higher card",
-                        c
-                    )));
-                }
-                Value::N(n) => if n <= hn {
-                    return Err(GameError::invalid_input(format!(
-                        "you can't play {} as you've already played a higher card",
-                        c
-                    )));
-                },
-            }
+        if !self.can_play(player, c) {
+            return Err(GameError::invalid_input(format!(
+                "you can't play {} as you've already played a higher card",
+                c
+            )));
         }
-        if self.expeditions
-            .get(player)
-            .ok_or_else(|| {
-                GameError::internal(format!(
-                    "could not find player expedition for player {}",
-                    player
-                ))
-            })?
-            .is_empty()
-        {
+        let mut undo = self.undo_point(player, self.hand_index(player, c)?);
+        let expedition = self.expeditions.get(player).ok_or_else(|| {
+            GameError::internal(format!(
+                "could not find player expedition for player {}",
+                player
+            ))
+        })?;
+        if expedition.is_empty() {
             self.stats[player].expeditions += 1;
         }
+        if expedition.iter().all(|ec| ec.expedition != c.expedition) {
+            self.stats[player].expeditions_opened += 1;
+        }
         self.remove_player_card(player, c)?;
+        undo.revealed = self.unreveal(player, c);
         self.expeditions
             .get_mut(player)
             .ok_or_else(|| {
@@ -377,6 +622,7 @@ impl Game {
             .push(c);
         self.next_phase();
         self.stats[player].plays += 1;
+        self.record_move(player, Command::Play(c), Some(undo));
         Ok(vec![Log::public(vec![
             N::Player(player),
             N::text(" played "),
@@ -388,7 +634,7 @@ impl Game {
         let mut logs: Vec<Log> = vec![];
         match self.hands.get_mut(player) {
             Some(hand) => {
-                let mut num = hand_size(self.players) - hand.len();
+                let mut num = self.options.hand_size - hand.len();
                 let dl = self.deck.len();
                 if num > dl {
                     num = dl;
@@ -471,10 +717,18 @@ impl Game {
         stats
     }
 
+    /// Placings for each player, in solo games the rival is placed too so the
+    /// player comes second if the rival beat them.
     fn placings(&self) -> Vec<usize> {
-        gen_placings(&(0..self.players)
+        let mut scores: Vec<Vec<i32>> = (0..self.players)
             .map(|p| vec![self.player_score(p) as i32])
-            .collect::<Vec<Vec<i32>>>())
+            .collect();
+        if let Some(ref rival) = self.rival {
+            scores.push(vec![rival.score() as i32]);
+        }
+        let mut placings = gen_placings(&scores);
+        placings.truncate(self.players);
+        placings
     }
 }
 
@@ -483,35 +737,14 @@ impl Gamer for Game {
     type PlayerState = PlayerState;
 
     fn new(players: usize) -> Result<(Self, Vec<Log>), GameError> {
-        if players < MIN_PLAYERS || players > MAX_PLAYERS {
-            return Err(GameError::PlayerCount {
-                min: MIN_PLAYERS,
-                max: MAX_PLAYERS,
-                given: players,
-            });
-        }
-        let mut stats = vec![];
-        let mut scores = vec![];
-        for _ in 0..players {
-            stats.push(Stats::default());
-            scores.push(vec![]);
-        }
-        let mut g = Game {
-            players,
-            round: START_ROUND,
-            stats,
-            scores,
-            ..Game::default()
-        };
-        let logs = g.start_round()?;
-        Ok((g, logs))
+        Game::new_with_seed(players, thread_rng().gen())
     }
 
     fn status(&self) -> Status {
-        if self.round >= START_ROUND + ROUNDS {
+        if self.round >= START_ROUND + self.options.rounds {
             Status::Finished {
                 placings: self.placings(),
-                stats: vec![self.player_stats(0), self.player_stats(1)],
+                stats: (0..self.players).map(|p| self.player_stats(p)).collect(),
             }
         } else {
             Status::Active {
@@ -530,16 +763,50 @@ impl Gamer for Game {
             deck_remaining: self.deck.len(),
             discards: {
                 let mut d: HashMap<Expedition, Value> = HashMap::new();
-                for e in card::expeditions() {
+                for e in self.options.expeditions() {
                     if let Some(c) = card::last_expedition(&self.discards, e) {
                         d.insert(e, c.value);
                     }
                 }
                 d
             },
+            discard_sizes: self.options
+                .expeditions()
+                .into_iter()
+                .map(|e| (e, self.discards.iter().filter(|c| c.expedition == e).count()))
+                .collect(),
+            discard_piles: if self.options.open_discards {
+                Some(
+                    self.options
+                        .expeditions()
+                        .into_iter()
+                        .map(|e| (e, card::of_expedition(&self.discards, e)))
+                        .collect(),
+                )
+            } else {
+                None
+            },
             scores: self.scores.clone(),
             expeditions: self.expeditions.clone(),
             current_player: self.current_player,
+            discarded_expedition: self.discarded_expedition,
+            options: self.options.clone(),
+            score_breakdowns: self.expeditions
+                .iter()
+                .map(|e| self.options.score_breakdown(e))
+                .collect(),
+            revealed: self.revealed.clone(),
+            rival: self.rival.as_ref().map(|r| r.state()),
+            hands: if self.options.open_information {
+                Some(self.hands.clone())
+            } else {
+                None
+            },
+            deck: if self.options.open_information {
+                Some(self.deck.clone())
+            } else {
+                None
+            },
         }
     }
 
@@ -548,6 +815,14 @@ impl Gamer for Game {
             public: self.pub_state(),
             player,
             hand: self.hands[player].clone(),
+            unseen: {
+                let unseen = self.unseen_cards(player);
+                self.options
+                    .expeditions()
+                    .into_iter()
+                    .map(|e| (e, card::of_expedition(&unseen, e)))
+                    .collect()
+            },
         }
     }
 
@@ -557,53 +832,46 @@ impl Gamer for Game {
         input: &str,
         players: &[String],
     ) -> Result<CommandResponse, GameError> {
-        let cp = match self.command_parser(player) {
-            Some(cp) => cp,
-            None => return Err(GameError::invalid_input("not your turn")),
+        let cp = self
+            .input_parser(player)
+            .ok_or_else(|| GameError::invalid_input("not your turn"))?;
+        let output = cp.parse(input, players)?;
+        let remaining = output.remaining.to_string();
+        let command = match output.value {
+            Input::Move(command) => command,
+            Input::Hint => {
+                return self.hint(player).map(|logs| CommandResponse {
+                    logs,
+                    can_undo: false,
+                    remaining_input: remaining,
+                })
+            }
         };
-        match cp.parse(input, players) {
-            Ok(ParseOutput {
-                value: Command::Play(c),
-                remaining,
-                ..
-            }) => self.play(player, c).map(|l| CommandResponse {
-                logs: l,
-                can_undo: true,
-                remaining_input: remaining.to_string(),
-            }),
-            Ok(ParseOutput {
-                value: Command::Discard(c),
-                remaining,
-                ..
-            }) => self.discard(player, c).map(|l| CommandResponse {
-                logs: l,
-                can_undo: true,
-                remaining_input: remaining.to_string(),
-            }),
-            Ok(ParseOutput {
-                value: Command::Take(e),
-                remaining,
-                ..
-            }) => self.take(player, e).map(|l| CommandResponse {
-                logs: l,
-                can_undo: true,
-                remaining_input: remaining.to_string(),
-            }),
-            Ok(ParseOutput {
-                value: Command::Draw,
-                remaining,
-                ..
-            }) => self.draw(player).map(|l| CommandResponse {
-                logs: l,
-                can_undo: false,
-                remaining_input: remaining.to_string(),
+        match command::turn_rest(&remaining) {
+            Some(rest) if command.starts_turn() => {
+                // A whole turn was given, only change the game if both halves
+                // are valid so the player isn't left stuck mid turn.
+                let mut next = self.clone();
+                let mut logs = next.apply_command(player, command)?;
+                let (second, remaining) = next.parse_command(player, rest, players)?;
+                logs.extend(next.apply_command(player, second)?);
+                *self = next;
+                Ok(CommandResponse {
+                    logs,
+                    can_undo: second.can_undo() && self.last_move_can_undo(),
+                    remaining_input: remaining,
+                })
+            }
+            _ => self.apply_command(player, command).map(|logs| CommandResponse {
+                logs,
+                can_undo: command.can_undo() && self.last_move_can_undo(),
+                remaining_input: remaining,
             }),
-            Err(e) => Err(e),
         }
     }
 
     fn command_spec(&self, player: usize) -> Option<CommandSpec> {
-        self.command_parser(player).map(|cp| cp.to_spec())
+        self.input_parser(player).map(|cp| cp.to_spec())
     }
 
     fn points(&self) -> Vec<f32> {
@@ -613,7 +881,7 @@ impl Gamer for Game {
     }
 
     fn player_counts() -> Vec<usize> {
-        (MIN_PLAYERS..MAX_PLAYERS).collect()
+        (MIN_PLAYERS..=MAX_PLAYERS).collect()
     }
 
     fn player_count(&self) -> usize {
@@ -625,63 +893,16 @@ fn next_player(player: usize, players: usize) -> usize {
     (player + 1) % players
 }
 
-fn expedition_cost(players: usize) -> isize {
-    match players {
-        2 => EXP_COST_2P,
-        3 => EXP_COST_3P,
-        _ => unreachable!(),
-    }
-}
-
-fn hand_size(players: usize) -> usize {
-    match players {
-        2 => HAND_SIZE_2P,
-        3 => HAND_SIZE_3P,
-        _ => unreachable!(),
-    }
-}
-
-fn expedition_bonus_size(players: usize) -> isize {
-    match players {
-        2 => EXP_BONUS_SIZE_2P,
-        3 => EXP_BONUS_SIZE_3P,
-        _ => unreachable!(),
-    }
+/// Score a set of expedition cards using the standard rules for the number of
+/// players, panicking if the number of players isn't supported.
+pub fn score(players: usize, cards: &[Card]) -> isize {
+    try_score(players, cards).expect("unsupported number of players")
 }
 
-pub fn score(players: usize, cards: &[Card]) -> isize {
-    let exp_cost = expedition_cost(players);
-    let exp_bonus_size = expedition_bonus_size(players);
-
-    let mut exp_cards: HashMap<Expedition, isize> = HashMap::new();
-    let mut exp_inv: HashMap<Expedition, isize> = HashMap::new();
-    let mut exp_sum: HashMap<Expedition, isize> = HashMap::new();
-    for c in cards {
-        let cards = exp_cards.entry(c.expedition).or_insert(0);
-        *cards += 1;
-        match c.value {
-            Value::Investment => {
-                let inv = exp_inv.entry(c.expedition).or_insert(0);
-                *inv += 1;
-            }
-            Value::N(n) => {
-                let sum = exp_sum.entry(c.expedition).or_insert(0);
-                *sum += n as isize;
-            }
-        }
-    }
-    expeditions().iter().fold(0, |acc, &e| {
-        let cards = exp_cards.get(&e);
-        if cards == None {
-            return acc;
-        }
-        acc + (exp_sum.get(&e).unwrap_or(&0) - exp_cost) * (exp_inv.get(&e).unwrap_or(&0) + 1)
-            + if cards.unwrap() >= &exp_bonus_size {
-                exp_cost
-            } else {
-                0
-            }
-    })
+/// Score cards with the standard rules for the number of players, failing if
+/// the number of players isn't supported.
+pub fn try_score(players: usize, cards: &[Card]) -> Result<isize, GameError> {
+    GameOptions::for_players(players).map(|o| o.score(cards))
 }
 
 #[cfg(test)]
@@ -722,6 +943,140 @@ mod test {
         assert_eq!(game.scores, vec![vec![0], vec![0]]);
     }
 
+    #[test]
+    fn start_3p_works() {
+        let game = Game::new(3).unwrap().0;
+        assert_eq!(game.hands.len(), 3);
+        for h in &game.hands {
+            assert_eq!(h.len(), HAND_SIZE_3P);
+        }
+        assert_eq!(game.deck.len(), 60 - 3 * HAND_SIZE_3P);
+    }
+
+    #[test]
+    fn end_round_3p_works() {
+        let mut game = Game::new(3).unwrap().0;
+        for _ in 0..(60 - 3 * HAND_SIZE_3P) {
+            let p = game.current_player;
+            let c = game.hands[p][0];
+            game.discard(p, c).unwrap();
+            assert_eq!(START_ROUND, game.round);
+            game.draw(p).unwrap();
+        }
+        assert_eq!(START_ROUND + 1, game.round);
+        for h in &game.hands {
+            assert_eq!(h.len(), HAND_SIZE_3P);
+        }
+        assert_eq!(game.deck.len(), 60 - 3 * HAND_SIZE_3P);
+        assert_eq!(game.scores, vec![vec![0], vec![0], vec![0]]);
+    }
+
+    #[test]
+    fn game_end_3p_works() {
+        let mut game = Game::new(3).unwrap().0;
+        for _ in 0..((60 - 3 * HAND_SIZE_3P) * ROUNDS) {
+            let p = game.current_player;
+            let c = game.hands[p][0];
+            game.discard(p, c).unwrap();
+            game.draw(p).unwrap();
+        }
+        assert_eq!(game.is_finished(), true);
+        match game.status() {
+            Status::Finished { stats, .. } => assert_eq!(3, stats.len()),
+            _ => panic!("expected the game to be finished"),
+        }
+    }
+
+    #[test]
+    fn deal_is_stable() {
+        let game = Game::new_with_seed(2, 42).unwrap().0;
+        // Saved seeds must deal the same cards on every platform.
+        let hand: Vec<String> = game.hands[0].iter().map(|c| c.to_string()).collect();
+        assert_eq!(vec!["R2", "BX", "R4", "R6", "YX", "G8", "WX", "YX"], hand);
+    }
+
+    #[test]
+    fn unseen_cards_works() {
+        let mut game = Game::new_with_seed(2, 1).unwrap().0;
+        assert_eq!(52, game.unseen_cards(0).len());
+        let c = game.hands[0][0];
+        discard_and_draw(&mut game, 0);
+        // Player 0 saw the card they drew, player 1 saw the discard.
+        assert_eq!(51, game.unseen_cards(0).len());
+        assert_eq!(51, game.unseen_cards(1).len());
+        let other = *game.hands[1]
+            .iter()
+            .find(|oc| oc.expedition != c.expedition)
+            .unwrap();
+        game.discard(1, other).unwrap();
+        game.take(1, c.expedition).unwrap();
+        // Everyone saw the card being taken, so it stays seen.
+        assert!(!game.unseen_cards(0).contains(&c));
+        assert_eq!(50, game.unseen_cards(0).len());
+        let ps = game.player_state(0);
+        assert_eq!(50, ps.unseen.values().map(|u| u.len()).sum::<usize>());
+        assert!(ps.unseen[&c.expedition].iter().all(|uc| uc.expedition == c.expedition));
+        // Once it leaves the hand it's no longer revealed.
+        game.discard(0, game.hands[0][0]).unwrap();
+        game.draw(0).unwrap();
+        game.discard(1, c).unwrap();
+        assert!(game.revealed[1].is_empty());
+        assert!(!game.unseen_cards(0).contains(&c));
+    }
+
+    #[test]
+    fn discard_piles_works() {
+        let mut game = Game::new_with_seed(2, 1).unwrap().0;
+        let red: Vec<Card> = vec![
+            (Expedition::Red, Value::N(3)).into(),
+            (Expedition::Red, Value::N(8)).into(),
+        ];
+        game.discards = red.clone();
+        let ps = game.pub_state();
+        assert_eq!(2, ps.discard_sizes[&Expedition::Red]);
+        assert_eq!(0, ps.discard_sizes[&Expedition::Blue]);
+        assert_eq!(Value::N(8), ps.discards[&Expedition::Red]);
+        assert_eq!(None, ps.discard_piles);
+        game.options.open_discards = true;
+        let piles = game.pub_state().discard_piles.unwrap();
+        assert_eq!(red, piles[&Expedition::Red]);
+        assert!(piles[&Expedition::Blue].is_empty());
+    }
+
+    #[test]
+    fn open_information_works() {
+        let mut game = Game::new_with_seed(2, 1).unwrap().0;
+        let ps = game.pub_state();
+        assert_eq!(None, ps.hands);
+        assert_eq!(None, ps.deck);
+        game.options.open_information = true;
+        let ps = game.pub_state();
+        assert_eq!(Some(game.hands.clone()), ps.hands);
+        assert_eq!(Some(game.deck.clone()), ps.deck);
+        assert!(game.player_state(0).unseen.values().all(|u| u.is_empty()));
+    }
+
+    #[test]
+    fn expedition_stats_works() {
+        let mut game = Game::new_with_seed(2, 1).unwrap().0;
+        game.hands[0][0] = (Expedition::Red, Value::N(3)).into();
+        game.hands[0][1] = (Expedition::Red, Value::N(5)).into();
+        game.hands[0][2] = (Expedition::Blue, Value::N(5)).into();
+        for &c in &game.hands[0][..3].to_vec() {
+            game.play(0, c).unwrap();
+            game.draw(0).unwrap();
+            discard_and_draw(&mut game, 1);
+        }
+        assert_eq!(1, game.stats[0].expeditions);
+        assert_eq!(2, game.stats[0].expeditions_opened);
+        assert_eq!(3, game.stats[0].plays);
+    }
+
+    #[test]
+    fn player_counts_works() {
+        assert_eq!(vec![1, 2, 3], Game::player_counts());
+    }
+
     #[test]
     fn game_end_works() {
         let mut game = Game::new(2).unwrap().0;
@@ -734,6 +1089,75 @@ mod test {
         assert_eq!(game.is_finished(), true);
     }
 
+    #[test]
+    fn seed_is_reproducible() {
+        let mut a = Game::new_with_seed(2, 1234).unwrap().0;
+        let mut b = Game::new_with_seed(2, 1234).unwrap().0;
+        assert_eq!(a, b);
+        // Play through the first round, the second round should also match.
+        for _ in 0..44 {
+            let p = a.current_player;
+            discard_and_draw(&mut a, p);
+            discard_and_draw(&mut b, p);
+        }
+        assert_eq!(START_ROUND + 1, a.round);
+        assert_eq!(a, b);
+        let c = Game::new_with_seed(2, 4321).unwrap().0;
+        assert_ne!(a.hands, c.hands);
+    }
+
+    #[test]
+    fn options_works() {
+        let mut game = Game::new_with_options(
+            2,
+            GameOptions {
+                rounds: 1,
+                investments: 4,
+                ..GameOptions::default()
+            },
+            1,
+        ).unwrap()
+            .0;
+        assert_eq!(game.deck.len(), 49);
+        for _ in 0..49 {
+            let p = game.current_player;
+            discard_and_draw(&mut game, p);
+        }
+        assert_eq!(game.is_finished(), true);
+        assert_eq!(game.scores, vec![vec![0], vec![0]]);
+    }
+
+    #[test]
+    fn sixth_expedition_works() {
+        let mut game = Game::new_with_options(
+            2,
+            GameOptions {
+                sixth_expedition: true,
+                ..GameOptions::default()
+            },
+            1,
+        ).unwrap()
+            .0;
+        assert_eq!(game.deck.len(), 56);
+        game.hands[0] = vec![
+            (Expedition::Purple, Value::Investment).into(),
+            (Expedition::Purple, Value::N(4)).into(),
+        ];
+        game.play(0, (Expedition::Purple, Value::Investment).into())
+            .unwrap();
+        game.draw(0).unwrap();
+        discard_and_draw(&mut game, 1);
+        game.play(0, (Expedition::Purple, Value::N(4)).into())
+            .unwrap();
+        assert_eq!(
+            game.pub_state().expeditions[0],
+            vec![
+                (Expedition::Purple, Value::Investment).into(),
+                (Expedition::Purple, Value::N(4)).into(),
+            ]
+        );
+    }
+
     #[test]
     fn play_works() {
         let mut game = Game::new(2).unwrap().0;
@@ -775,6 +1199,21 @@ mod test {
         );
     }
 
+    #[test]
+    fn deserialize_defaults_options_for_players() {
+        let game = Game::new_with_seed(3, 1).unwrap().0;
+        let mut saved = serde_json::to_value(&game).unwrap();
+        saved.as_object_mut().unwrap().remove("options");
+        let loaded: Game = serde_json::from_value(saved).unwrap();
+        assert_eq!(GameOptions::for_players(3).unwrap(), loaded.options);
+        assert_eq!(game, loaded);
+        // There are no rules to fall back to for unsupported player counts.
+        let mut saved = serde_json::to_value(&game).unwrap();
+        saved.as_object_mut().unwrap().remove("options");
+        saved["players"] = 4.into();
+        assert!(serde_json::from_value::<Game>(saved).is_err());
+    }
+
     #[test]
     fn score_works() {
         assert_eq!(0, score(2, &vec![]));
@@ -830,6 +1269,12 @@ mod test {
         );
     }
 
+    #[test]
+    fn try_score_works() {
+        assert_eq!(-17, try_score(2, &[(Expedition::Red, Value::N(3)).into()]).unwrap());
+        assert!(try_score(4, &[]).is_err());
+    }
+
     #[test]
     fn placings_works() {
         let mut g = Game::new(2).expect("expected to create game").0;
@@ -839,5 +1284,13 @@ mod test {
         assert_eq!(vec![2, 1], g.placings());
         g.scores = vec![vec![100, 50, 40], vec![100, 50, 40]];
         assert_eq!(vec![1, 1], g.placings());
+        let mut g = Game::new(1).expect("expected to create game").0;
+        g.scores = vec![vec![20, 10, 0]];
+        g.rival.as_mut().unwrap().scores = vec![40, 0, 0];
+        assert_eq!(vec![2], g.placings());
+        g.rival.as_mut().unwrap().scores = vec![30, 0, 0];
+        assert_eq!(vec![1], g.placings());
+        g.rival.as_mut().unwrap().scores = vec![0, 0, 0];
+        assert_eq!(vec![1], g.placings());
     }
 }
@@ -0,0 +1,309 @@
+//! A compact, human readable notation for whole games, similar to chess's
+//! PGN. A header of tags gives the players, seed and options, followed by
+//! one line per turn:
+//!
+//! ```text
+//! [Players "2"]
+//! [Seed "42"]
+//! [Rounds "3"]
+//!
+//! 1. P0 play R5 / draw
+//! P1 discard G3 / take B
+//! 2. P0 play RX / draw
+//! ```
+//!
+//! Commands are written exactly as players type them, so each half of a turn
+//! is parsed with the game's own command parser. Tags left out of the header
+//! use the standard rules for the number of players.
+
+use brdgme_game::errors::GameError;
+
+use crate::command::Command;
+use crate::options::GameOptions;
+use crate::Game;
+
+use std::fmt::Write;
+
+/// Separates the two halves of a turn.
+const TURN_SEPARATOR: &str = " / ";
+
+fn tags(game: &Game) -> Vec<(&'static str, String)> {
+    let o = &game.options;
+    vec![
+        ("Players", game.players.to_string()),
+        ("Seed", game.seed.to_string()),
+        ("Rounds", o.rounds.to_string()),
+        ("HandSize", o.hand_size.to_string()),
+        ("Investments", o.investments.to_string()),
+        ("MinValue", o.min_value.to_string()),
+        ("MaxValue", o.max_value.to_string()),
+        ("ExpeditionCost", o.expedition_cost.to_string()),
+        ("ExpeditionBonusSize", o.expedition_bonus_size.to_string()),
+        ("ExpeditionBonus", o.expedition_bonus.to_string()),
+        ("SixthExpedition", o.sixth_expedition.to_string()),
+        ("CardTracker", o.card_tracker.to_string()),
+        ("OpenDiscards", o.open_discards.to_string()),
+        ("OpenInformation", o.open_information.to_string()),
+    ]
+}
+
+/// Split the history into turns, each starting with a play or discard.
+fn turns(commands: &[(usize, Command)]) -> Vec<&[(usize, Command)]> {
+    let mut turns = vec![];
+    let mut start = 0;
+    for (i, &(_, command)) in commands.iter().enumerate() {
+        if i > start && command.starts_turn() {
+            turns.push(&commands[start..i]);
+            start = i;
+        }
+    }
+    if start < commands.len() {
+        turns.push(&commands[start..]);
+    }
+    turns
+}
+
+fn invalid(line: usize, message: &str) -> GameError {
+    GameError::invalid_input(format!("line {}: {}", line, message))
+}
+
+fn parse_value<T: std::str::FromStr>(
+    line: usize,
+    name: &str,
+    value: &str,
+) -> Result<T, GameError> {
+    value
+        .parse()
+        .map_err(|_| invalid(line, &format!("invalid value for {}: {}", name, value)))
+}
+
+/// Parse a `[Name "value"]` tag.
+fn parse_tag(line: usize, text: &str) -> Result<(String, String), GameError> {
+    let inner = text.trim_start_matches('[').trim_end_matches(']').trim();
+    let mut parts = inner.splitn(2, char::is_whitespace);
+    let name = parts.next().unwrap_or("");
+    let value = parts.next().unwrap_or("").trim();
+    if name.is_empty() || value.len() < 2 || !value.starts_with('"') || !value.ends_with('"') {
+        return Err(invalid(line, &format!("invalid tag: {}", text)));
+    }
+    Ok((name.to_string(), value[1..value.len() - 1].to_string()))
+}
+
+fn set_option(
+    options: &mut GameOptions,
+    line: usize,
+    name: &str,
+    value: &str,
+) -> Result<(), GameError> {
+    match name {
+        "Rounds" => options.rounds = parse_value(line, name, value)?,
+        "HandSize" => options.hand_size = parse_value(line, name, value)?,
+        "Investments" => options.investments = parse_value(line, name, value)?,
+        "MinValue" => options.min_value = parse_value(line, name, value)?,
+        "MaxValue" => options.max_value = parse_value(line, name, value)?,
+        "ExpeditionCost" => options.expedition_cost = parse_value(line, name, value)?,
+        "ExpeditionBonusSize" => {
+            options.expedition_bonus_size = parse_value(line, name, value)?
+        }
+        "ExpeditionBonus" => options.expedition_bonus = parse_value(line, name, value)?,
+        "SixthExpedition" => options.sixth_expedition = parse_value(line, name, value)?,
+        "CardTracker" => options.card_tracker = parse_value(line, name, value)?,
+        "OpenDiscards" => options.open_discards = parse_value(line, name, value)?,
+        "OpenInformation" => options.open_information = parse_value(line, name, value)?,
+        _ => return Err(invalid(line, &format!("unknown tag: {}", name))),
+    }
+    Ok(())
+}
+
+impl Game {
+    /// The game so far in notation, undone moves are left out. Games built
+    /// from a position can't be written as notation as it only records the
+    /// seed, use a replay instead.
+    pub fn to_notation(&self) -> Result<String, GameError> {
+        if self.start.is_some() {
+            return Err(GameError::invalid_input(
+                "games built from a position can't be written as notation",
+            ));
+        }
+        let mut out = String::new();
+        for (name, value) in tags(self) {
+            writeln!(out, "[{} \"{}\"]", name, value).unwrap();
+        }
+        let rounds = self.round_commands();
+        if !rounds.is_empty() {
+            out.push('\n');
+        }
+        let mut number = 0;
+        for round in &rounds {
+            // Turns are numbered from whoever started the round.
+            let starter = round[0].0;
+            for turn in turns(round) {
+                if turn[0].0 == starter {
+                    number += 1;
+                    write!(out, "{}. ", number).unwrap();
+                }
+                let commands: Vec<String> = turn.iter().map(|&(_, c)| c.to_string()).collect();
+                writeln!(out, "P{} {}", turn[0].0, commands.join(TURN_SEPARATOR)).unwrap();
+            }
+        }
+        Ok(out)
+    }
+
+    /// Rebuild a game from notation, checking every command is valid.
+    pub fn from_notation(notation: &str) -> Result<Game, GameError> {
+        let mut lines = notation.lines().enumerate().map(|(i, l)| (i + 1, l.trim()));
+        let mut players: Option<usize> = None;
+        let mut seed: Option<u64> = None;
+        let mut option_tags: Vec<(usize, String, String)> = vec![];
+        let mut turn_lines: Vec<(usize, &str)> = vec![];
+        for (n, line) in &mut lines {
+            if line.is_empty() {
+                continue;
+            }
+            if !line.starts_with('[') {
+                turn_lines.push((n, line));
+                break;
+            }
+            let (name, value) = parse_tag(n, line)?;
+            match name.as_ref() {
+                "Players" => players = Some(parse_value(n, &name, &value)?),
+                "Seed" => seed = Some(parse_value(n, &name, &value)?),
+                _ => option_tags.push((n, name, value)),
+            }
+        }
+        turn_lines.extend(lines.filter(|&(_, l)| !l.is_empty()));
+
+        let players = players.ok_or_else(|| GameError::invalid_input("missing Players tag"))?;
+        let seed = seed.ok_or_else(|| GameError::invalid_input("missing Seed tag"))?;
+        let mut options = GameOptions::for_players(players)?;
+        for (n, name, value) in &option_tags {
+            set_option(&mut options, *n, name, value)?;
+        }
+        let mut game = Game::new_with_options(players, options, seed)?.0;
+
+        for (n, line) in turn_lines {
+            // Skip the turn number.
+            let mut rest = line;
+            if let Some(i) = rest.find(". ") {
+                if rest[..i].chars().all(|c| c.is_ascii_digit()) {
+                    rest = rest[i + 2..].trim_start();
+                }
+            }
+            let mut parts = rest.splitn(2, ' ');
+            let player: usize = match parts.next() {
+                Some(p) if p.starts_with('P') => parse_value(n, "player", &p[1..])?,
+                _ => return Err(invalid(n, "expected a player such as P0")),
+            };
+            for text in parts.next().unwrap_or("").split(TURN_SEPARATOR.trim()) {
+                let text = text.trim();
+                let (command, remaining) = game
+                    .parse_command(player, text, &[])
+                    .map_err(|e| invalid(n, &format!("{}: {}", text, e)))?;
+                if !remaining.trim().is_empty() {
+                    return Err(invalid(n, &format!("unexpected input: {}", remaining)));
+                }
+                game.apply_command(player, command)
+                    .map_err(|e| invalid(n, &format!("{}: {}", text, e)))?;
+            }
+        }
+        Ok(game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::{Bot, HeuristicBot};
+    use brdgme_game::Gamer;
+
+    #[test]
+    fn notation_works() {
+        let mut options = GameOptions::for_players(3).unwrap();
+        options.sixth_expedition = true;
+        let mut game = Game::new_with_options(3, options, 5).unwrap().0;
+        while !game.is_finished() {
+            let p = game.current_player;
+            let command = HeuristicBot.choose(&game.player_state(p));
+            game.apply_command(p, command).unwrap();
+        }
+        let notation = game.to_notation().unwrap();
+        assert!(notation.contains("[SixthExpedition \"true\"]"));
+        assert_eq!(game, Game::from_notation(&notation).unwrap());
+    }
+
+    #[test]
+    fn to_notation_works() {
+        let mut game = Game::new_with_seed(2, 1).unwrap().0;
+        let first = game.hands[0][0];
+        game.discard(0, first).unwrap();
+        game.draw(0).unwrap();
+        let other = *game.hands[1]
+            .iter()
+            .find(|oc| oc.expedition != first.expedition)
+            .unwrap();
+        game.discard(1, other).unwrap();
+        game.take(1, first.expedition).unwrap();
+        let last = game.hands[0][0];
+        game.discard(0, last).unwrap();
+        let notation = game.to_notation().unwrap();
+        let turns: Vec<&str> = notation
+            .lines()
+            .skip_while(|l| !l.is_empty())
+            .skip(1)
+            .collect();
+        assert_eq!(
+            vec![
+                format!("1. P0 discard {} / draw", first),
+                format!("P1 discard {} / take {}", other, first.expedition),
+                format!("2. P0 discard {}", last),
+            ],
+            turns
+        );
+        assert_eq!(game, Game::from_notation(&notation).unwrap());
+    }
+
+    #[test]
+    fn to_notation_numbers_turns_from_the_round_starter() {
+        let mut game = Game::new_with_seed(2, 1).unwrap().0;
+        let first = game.hands[0][0];
+        game.discard(0, first).unwrap();
+        game.draw(0).unwrap();
+        let other = *game.hands[1]
+            .iter()
+            .find(|oc| oc.expedition != first.expedition)
+            .unwrap();
+        game.discard(1, other).unwrap();
+        game.take(1, first.expedition).unwrap();
+        // The take leaves an odd number of turns, so the next round starts
+        // with player 1.
+        let round = game.round;
+        while game.round == round {
+            let p = game.current_player;
+            let c = game.hands[p][0];
+            game.discard(p, c).unwrap();
+            game.draw(p).unwrap();
+        }
+        assert_eq!(1, game.current_player);
+        let c = game.hands[1][0];
+        game.discard(1, c).unwrap();
+        let notation = game.to_notation().unwrap();
+        assert_eq!(Some(format!("24. P1 discard {}", c).as_str()), notation.lines().last());
+        assert_eq!(game, Game::from_notation(&notation).unwrap());
+    }
+
+    #[test]
+    fn from_notation_defaults_options() {
+        let game = Game::from_notation("[Players \"3\"]\n[Seed \"2\"]\n").unwrap();
+        assert_eq!(GameOptions::for_players(3).unwrap(), game.options);
+        assert_eq!(2, game.seed);
+    }
+
+    #[test]
+    fn from_notation_rejects_invalid_input() {
+        assert!(Game::from_notation("[Seed \"2\"]").is_err());
+        assert!(Game::from_notation("[Players \"2\"]\n[Seed \"2\"]\n[Bogus \"1\"]").is_err());
+        let notation = "[Players \"2\"]\n[Seed \"1\"]\n\n1. P1 draw\n";
+        let err = Game::from_notation(notation).unwrap_err();
+        assert!(err.to_string().contains("line 4"), "{}", err);
+    }
+}
@@ -0,0 +1,206 @@
+use serde_derive::{Deserialize, Serialize};
+
+use brdgme_game::errors::GameError;
+
+use crate::card::{all_expeditions, expeditions, Card, Expedition, Value};
+use crate::{
+    EXP_BONUS_SIZE_2P, EXP_BONUS_SIZE_3P, EXP_COST_2P, EXP_COST_3P, HAND_SIZE_2P, HAND_SIZE_3P,
+    INVESTMENTS, MAX_PLAYERS, MAX_VALUE, MIN_PLAYERS, MIN_VALUE, ROUNDS,
+};
+
+/// Rules for a game, stored in the game so house rules can be played without
+/// changing the engine.
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct GameOptions {
+    pub rounds: usize,
+    pub hand_size: usize,
+    pub investments: usize,
+    pub min_value: usize,
+    pub max_value: usize,
+    pub expedition_cost: isize,
+    pub expedition_bonus_size: usize,
+    pub expedition_bonus: isize,
+    /// Play with the purple expedition from the modern edition.
+    #[serde(default)]
+    pub sixth_expedition: bool,
+    /// Show players the cards they haven't seen yet this round.
+    #[serde(default)]
+    pub card_tracker: bool,
+    /// Make the full discard piles public instead of only the top cards.
+    #[serde(default)]
+    pub open_discards: bool,
+    /// Make every hand and the order of the deck public, for analysis and
+    /// teaching games.
+    #[serde(default)]
+    pub open_information: bool,
+}
+
+/// The standard two player rules.
+impl Default for GameOptions {
+    fn default() -> GameOptions {
+        GameOptions::standard(HAND_SIZE_2P, EXP_COST_2P, EXP_BONUS_SIZE_2P)
+    }
+}
+
+impl GameOptions {
+    /// The standard rules for the given number of players, solo games use the
+    /// two player rules.
+    pub fn for_players(players: usize) -> Result<GameOptions, GameError> {
+        match players {
+            1 | 2 => Ok(GameOptions::default()),
+            3 => Ok(GameOptions::standard(
+                HAND_SIZE_3P,
+                EXP_COST_3P,
+                EXP_BONUS_SIZE_3P,
+            )),
+            _ => Err(GameError::PlayerCount {
+                min: MIN_PLAYERS,
+                max: MAX_PLAYERS,
+                given: players,
+            }),
+        }
+    }
+
+    fn standard(
+        hand_size: usize,
+        expedition_cost: isize,
+        expedition_bonus_size: usize,
+    ) -> GameOptions {
+        GameOptions {
+            rounds: ROUNDS,
+            hand_size,
+            investments: INVESTMENTS,
+            min_value: MIN_VALUE,
+            max_value: MAX_VALUE,
+            expedition_cost,
+            expedition_bonus_size,
+            expedition_bonus: expedition_cost,
+            sixth_expedition: false,
+            card_tracker: false,
+            open_discards: false,
+            open_information: false,
+        }
+    }
+
+    /// The expeditions in play for these options.
+    pub fn expeditions(&self) -> Vec<Expedition> {
+        if self.sixth_expedition {
+            all_expeditions()
+        } else {
+            expeditions()
+        }
+    }
+
+    pub fn validate(&self, players: usize) -> Result<(), GameError> {
+        if self.rounds == 0 {
+            return Err(GameError::invalid_input("there must be at least one round"));
+        }
+        if self.hand_size == 0 {
+            return Err(GameError::invalid_input("hand size must be at least one"));
+        }
+        if self.min_value > self.max_value {
+            return Err(GameError::invalid_input(
+                "minimum card value can't be higher than the maximum",
+            ));
+        }
+        if self.deck_size() <= self.hand_size * players {
+            return Err(GameError::invalid_input(
+                "there aren't enough cards in the deck to deal every player a hand",
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn deck_size(&self) -> usize {
+        self.initial_deck().len()
+    }
+
+    pub fn initial_deck(&self) -> Vec<Card> {
+        let mut deck: Vec<Card> = vec![];
+        for e in self.expeditions() {
+            for _ in 0..self.investments {
+                deck.push((e, Value::Investment).into());
+            }
+            for v in self.min_value..self.max_value + 1 {
+                deck.push((e, Value::N(v)).into());
+            }
+        }
+        deck
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn for_players_works() {
+        let opts = GameOptions::for_players(2).unwrap();
+        assert_eq!(8, opts.hand_size);
+        assert_eq!(20, opts.expedition_cost);
+        assert_eq!(60, opts.deck_size());
+        assert_eq!(opts, GameOptions::for_players(1).unwrap());
+        assert_eq!(opts, GameOptions::default());
+        let opts = GameOptions::for_players(3).unwrap();
+        assert_eq!(7, opts.hand_size);
+        assert_eq!(15, opts.expedition_cost);
+        assert!(GameOptions::for_players(0).is_err());
+        assert!(GameOptions::for_players(4).is_err());
+    }
+
+    #[test]
+    fn sixth_expedition_works() {
+        let opts = GameOptions {
+            sixth_expedition: true,
+            ..GameOptions::default()
+        };
+        assert_eq!(6, opts.expeditions().len());
+        assert_eq!(72, opts.deck_size());
+        assert_eq!(
+            -17,
+            opts.score(&[(Expedition::Purple, Value::N(3)).into()])
+        );
+    }
+
+    #[test]
+    fn validate_works() {
+        assert!(GameOptions::default().validate(2).is_ok());
+        assert!(
+            GameOptions {
+                rounds: 0,
+                ..GameOptions::default()
+            }.validate(2)
+                .is_err()
+        );
+        assert!(
+            GameOptions {
+                min_value: 9,
+                max_value: 8,
+                ..GameOptions::default()
+            }.validate(2)
+                .is_err()
+        );
+        assert!(
+            GameOptions {
+                hand_size: 30,
+                ..GameOptions::default()
+            }.validate(2)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn bonus_size_works() {
+        let cards: Vec<Card> = (2..9)
+            .map(|v| (Expedition::Green, Value::N(v)).into())
+            .collect();
+        assert_eq!(15, GameOptions::default().score(&cards));
+        assert_eq!(
+            35,
+            GameOptions {
+                expedition_bonus_size: 7,
+                ..GameOptions::default()
+            }.score(&cards)
+        );
+    }
+}
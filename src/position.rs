@@ -0,0 +1,301 @@
+use serde_derive::{Deserialize, Serialize};
+
+use rand::Rng;
+
+use brdgme_game::errors::GameError;
+
+use crate::card::{Card, Expedition};
+use crate::options::GameOptions;
+use crate::solo::Rival;
+use crate::{Game, Phase, Stats, MAX_PLAYERS, MIN_PLAYERS, START_ROUND};
+
+use std::collections::HashMap;
+
+/// An arbitrary game position, for puzzles and tests which need control over
+/// every card.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Position {
+    pub players: usize,
+    /// The rules, or the standard rules for the number of players if left
+    /// out.
+    #[serde(default)]
+    pub options: Option<GameOptions>,
+    /// Used to shuffle cards which aren't placed anywhere, and to deal later
+    /// rounds.
+    #[serde(default)]
+    pub seed: u64,
+    pub round: usize,
+    #[serde(default)]
+    pub phase: Phase,
+    #[serde(default)]
+    pub current_player: usize,
+    pub hands: Vec<Vec<Card>>,
+    /// The top of the deck first. Cards not placed anywhere else are shuffled
+    /// and put underneath.
+    #[serde(default)]
+    pub deck: Vec<Card>,
+    pub expeditions: Vec<Vec<Card>>,
+    /// Each discard pile from bottom to top.
+    #[serde(default)]
+    pub discards: HashMap<Expedition, Vec<Card>>,
+    /// The scores of each completed round.
+    #[serde(default)]
+    pub scores: Vec<Vec<isize>>,
+    #[serde(default)]
+    pub discarded_expedition: Option<Expedition>,
+    /// Left out if no cards have been taken from the discards.
+    #[serde(default)]
+    pub revealed: Vec<Vec<Card>>,
+    /// Only allowed in solo games.
+    #[serde(default)]
+    pub rival: Option<Rival>,
+}
+
+impl Position {
+    /// An empty position at the start of the first round with the standard
+    /// rules.
+    pub fn new(players: usize) -> Position {
+        Position {
+            players,
+            options: None,
+            seed: 0,
+            round: START_ROUND,
+            phase: Phase::PlayOrDiscard,
+            current_player: 0,
+            hands: vec![vec![]; players],
+            deck: vec![],
+            expeditions: vec![vec![]; players],
+            discards: HashMap::new(),
+            scores: vec![vec![]; players],
+            discarded_expedition: None,
+            revealed: vec![vec![]; players],
+            rival: if players == 1 {
+                Some(Rival::default())
+            } else {
+                None
+            },
+        }
+    }
+}
+
+impl Game {
+    /// Build a game from a position, checking the result is a valid game.
+    pub fn from_position(position: &Position) -> Result<Game, GameError> {
+        let players = position.players;
+        if !(MIN_PLAYERS..=MAX_PLAYERS).contains(&players) {
+            return Err(GameError::PlayerCount {
+                min: MIN_PLAYERS,
+                max: MAX_PLAYERS,
+                given: players,
+            });
+        }
+        let options = match position.options {
+            Some(ref options) => options.clone(),
+            None => GameOptions::for_players(players)?,
+        };
+        options.validate(players)?;
+        if position.rival.is_some() && players != 1 {
+            return Err(GameError::invalid_input("only solo games can have a rival"));
+        }
+
+        // Keep the just discarded pile on top.
+        let mut piles: Vec<Expedition> = position.discards.keys().cloned().collect();
+        piles.sort_by_key(|&e| (Some(e) == position.discarded_expedition, e));
+        let mut discards: Vec<Card> = vec![];
+        for e in piles {
+            for &c in &position.discards[&e] {
+                if c.expedition != e {
+                    return Err(GameError::invalid_input(format!(
+                        "{} is in the {} discard pile",
+                        c,
+                        e.name().to_lowercase()
+                    )));
+                }
+                discards.push(c);
+            }
+        }
+
+        let mut game = Game {
+            players,
+            round: position.round,
+            phase: position.phase,
+            deck: position.deck.clone(),
+            discards,
+            hands: position.hands.clone(),
+            scores: position.scores.clone(),
+            expeditions: position.expeditions.clone(),
+            current_player: position.current_player,
+            discarded_expedition: position.discarded_expedition,
+            stats: vec![Stats::default(); players],
+            seed: position.seed,
+            options,
+            history: vec![],
+            past_rounds: vec![],
+            revealed: if position.revealed.is_empty() {
+                vec![vec![]; players]
+            } else {
+                position.revealed.clone()
+            },
+            rival: position.rival.clone(),
+            start: Some(position.clone()),
+        };
+
+        // Shuffle the remaining cards under the deck.
+        let mut rest = game.options.initial_deck();
+        for c in game
+            .deck
+            .iter()
+            .chain(game.discards.iter())
+            .chain(game.hands.iter().flat_map(|h| h.iter()))
+            .chain(game.expeditions.iter().flat_map(|e| e.iter()))
+            .chain(
+                game.rival
+                    .iter()
+                    .flat_map(|r| r.pile.iter().chain(r.expeditions.iter())),
+            )
+        {
+            if let Some(i) = rest.iter().position(|rc| rc == c) {
+                rest.remove(i);
+            }
+        }
+        game.round_rng().shuffle(rest.as_mut_slice());
+        game.deck.extend(rest);
+
+        let violations = game.validate();
+        if !violations.is_empty() {
+            let messages: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+            return Err(GameError::invalid_input(format!(
+                "invalid position: {}",
+                messages.join(", ")
+            )));
+        }
+        Ok(game)
+    }
+
+    /// The current position, which builds the same game without its history.
+    pub fn position(&self) -> Position {
+        let mut discards: HashMap<Expedition, Vec<Card>> = HashMap::new();
+        for &c in &self.discards {
+            discards.entry(c.expedition).or_default().push(c);
+        }
+        Position {
+            players: self.players,
+            options: Some(self.options.clone()),
+            seed: self.seed,
+            round: self.round,
+            phase: self.phase,
+            current_player: self.current_player,
+            hands: self.hands.clone(),
+            deck: self.deck.clone(),
+            expeditions: self.expeditions.clone(),
+            discards,
+            scores: self.scores.clone(),
+            discarded_expedition: self.discarded_expedition,
+            revealed: self.revealed.clone(),
+            rival: self.rival.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::card;
+    use brdgme_game::Gamer;
+
+    #[test]
+    fn from_position_works() {
+        let mut position = Position::new(2);
+        position.options = Some(GameOptions {
+            hand_size: 2,
+            ..GameOptions::default()
+        });
+        position.hands = vec![
+            vec![card(Expedition::Red, 5), card(Expedition::Red, 6)],
+            vec![card(Expedition::Blue, 2), card(Expedition::Blue, 3)],
+        ];
+        position.deck = vec![card(Expedition::Green, 10)];
+        position.expeditions[0] = vec![card(Expedition::Red, 4)];
+        position.discards.insert(
+            Expedition::White,
+            vec![card(Expedition::White, 2), card(Expedition::White, 3)],
+        );
+        let mut game = Game::from_position(&position).unwrap();
+        assert_eq!(60, game.deck.len() + 7);
+        assert_eq!(Some(&card(Expedition::White, 3)), game.discards.last());
+        game.play(0, card(Expedition::Red, 5)).unwrap();
+        game.draw(0).unwrap();
+        assert!(game.hands[0].contains(&card(Expedition::Green, 10)));
+        assert_eq!(position.hands[1], game.player_state(1).hand);
+        // Replays start from the position, which notation can't record.
+        assert_eq!(game, Game::from_replay(&game.replay()).unwrap());
+        assert!(game.to_notation().is_err());
+    }
+
+    #[test]
+    fn from_position_rejects_invalid_positions() {
+        let mut position = Position::new(2);
+        // The hands are empty.
+        assert!(Game::from_position(&position).is_err());
+        position.options = Some(GameOptions {
+            hand_size: 1,
+            ..GameOptions::default()
+        });
+        position.hands = vec![vec![card(Expedition::Red, 5)], vec![card(Expedition::Red, 5)]];
+        assert!(Game::from_position(&position).is_err());
+        position.hands[1] = vec![card(Expedition::Red, 6)];
+        position.expeditions[0] = vec![card(Expedition::Red, 7), card(Expedition::Red, 3)];
+        assert!(Game::from_position(&position).is_err());
+        position.expeditions[0] = vec![];
+        position
+            .discards
+            .insert(Expedition::Blue, vec![card(Expedition::Red, 7)]);
+        assert!(Game::from_position(&position).is_err());
+        position.discards.clear();
+        position.rival = Some(Rival::default());
+        assert!(Game::from_position(&position).is_err());
+        position.rival = None;
+        assert!(Game::from_position(&position).is_ok());
+        assert!(Game::from_position(&Position::new(0)).is_err());
+        assert!(Game::from_position(&Position::new(MAX_PLAYERS + 1)).is_err());
+    }
+
+    #[test]
+    fn from_position_solo_works() {
+        let mut position = Position::new(1);
+        position.hands = vec![(2..10).map(|v| card(Expedition::Red, v)).collect()];
+        let rival = position.rival.as_mut().unwrap();
+        rival.pile = vec![card(Expedition::Blue, 4)];
+        rival.expeditions = vec![card(Expedition::Green, 3)];
+        let mut game = Game::from_position(&position).unwrap();
+        game.play(0, card(Expedition::Red, 2)).unwrap();
+        game.draw(0).unwrap();
+        let rival = game.rival.as_ref().unwrap();
+        assert!(rival.pile.is_empty());
+        assert_eq!(
+            vec![card(Expedition::Green, 3), card(Expedition::Blue, 4)],
+            rival.expeditions
+        );
+        assert_eq!(0, game.current_player);
+    }
+
+    #[test]
+    fn position_works() {
+        let mut game = Game::new_with_seed(3, 4).unwrap().0;
+        let c = game.hands[0][0];
+        game.discard(0, c).unwrap();
+        let rebuilt = Game::from_position(&game.position()).unwrap();
+        assert_eq!(game.deck, rebuilt.deck);
+        assert_eq!(game.discards, rebuilt.discards);
+        assert_eq!(game.hands, rebuilt.hands);
+        assert_eq!(Some(c.expedition), rebuilt.discarded_expedition);
+        assert_eq!(game.position(), rebuilt.position());
+        // Left out options use the rules for the number of players.
+        let mut position = game.position();
+        position.options = None;
+        assert_eq!(
+            GameOptions::for_players(3).unwrap(),
+            Game::from_position(&position).unwrap().options
+        );
+    }
+}
@@ -0,0 +1,305 @@
+//! Property tests which play random legal command sequences and check
+//! invariants after every step, shrinking failures to a minimal replay.
+
+use rand::{Rng, SeedableRng, StdRng};
+
+use brdgme_game::Gamer;
+
+use crate::card::{Card, Expedition, Value};
+use crate::options::GameOptions;
+use crate::Game;
+
+use std::collections::BTreeMap;
+use std::env;
+
+const DEFAULT_CASES: usize = 10;
+const MAX_CHOICES: usize = 400;
+
+/// A generated test case, each choice picks a legal move by index so every
+/// sequence of choices is playable, which keeps shrunk cases valid.
+#[derive(Debug, Clone, PartialEq)]
+struct Case {
+    players: usize,
+    seed: u64,
+    choices: Vec<usize>,
+}
+
+#[derive(Debug)]
+struct Failure {
+    step: usize,
+    message: String,
+    game: Box<Game>,
+}
+
+type Property = fn(before: &Game, after: &Game) -> Result<(), String>;
+
+fn generate<R: Rng>(rng: &mut R) -> Case {
+    let len = rng.gen_range(1, MAX_CHOICES);
+    Case {
+        players: rng.gen_range(1, 4),
+        seed: rng.gen(),
+        choices: (0..len).map(|_| rng.gen_range(0, 16)).collect(),
+    }
+}
+
+fn run(case: &Case, properties: &[Property]) -> Result<(), Failure> {
+    let mut game = Game::new_with_seed(case.players, case.seed).unwrap().0;
+    for (step, &choice) in case.choices.iter().enumerate() {
+        if game.is_finished() {
+            break;
+        }
+        let p = game.current_player;
+        let moves = game.legal_moves(p);
+        let before = game.clone();
+        if moves.is_empty() {
+            return Err(Failure {
+                step,
+                message: format!("no legal moves for player {}", p),
+                game: Box::new(game),
+            });
+        }
+        let command = moves[choice % moves.len()];
+        if let Err(e) = game.apply_command(p, command) {
+            return Err(Failure {
+                step,
+                message: format!("legal move {} failed: {}", command, e),
+                game: Box::new(game),
+            });
+        }
+        for property in properties {
+            if let Err(message) = property(&before, &game) {
+                return Err(Failure {
+                    step,
+                    message,
+                    game: Box::new(game),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Candidate smaller cases, from the most to the least aggressive.
+fn shrink_candidates(case: &Case) -> Vec<Case> {
+    let mut candidates = vec![];
+    let len = case.choices.len();
+    // Remove chunks, halving the chunk size down to single choices.
+    let mut chunk = len / 2;
+    while chunk > 0 {
+        let mut start = 0;
+        while start < len {
+            let mut choices = case.choices.clone();
+            choices.drain(start..(start + chunk).min(len));
+            candidates.push(Case {
+                choices,
+                ..case.clone()
+            });
+            start += chunk;
+        }
+        chunk /= 2;
+    }
+    // Prefer the first legal move.
+    for i in 0..len {
+        if case.choices[i] > 0 {
+            let mut choices = case.choices.clone();
+            choices[i] = 0;
+            candidates.push(Case {
+                choices,
+                ..case.clone()
+            });
+        }
+    }
+    candidates
+}
+
+/// Greedily shrink a failing case until no candidate still fails.
+fn shrink(mut case: Case, properties: &[Property]) -> (Case, Failure) {
+    let mut failure = run(&case, properties).expect_err("case to shrink must fail");
+    // Choices after the failing step aren't used.
+    case.choices.truncate(failure.step + 1);
+    'outer: loop {
+        for candidate in shrink_candidates(&case) {
+            if let Err(f) = run(&candidate, properties) {
+                case = candidate;
+                case.choices.truncate(f.step + 1);
+                failure = f;
+                continue 'outer;
+            }
+        }
+        return (case, failure);
+    }
+}
+
+fn check(properties: &[Property]) {
+    let cases = env::var("LOST_CITIES_PROPERTY_CASES")
+        .ok()
+        .and_then(|c| c.parse().ok())
+        .unwrap_or(DEFAULT_CASES);
+    let mut rng = StdRng::from_seed(&[1][..]);
+    for _ in 0..cases {
+        let case = generate(&mut rng);
+        if run(&case, properties).is_err() {
+            let (case, failure) = shrink(case, properties);
+            panic!(
+                "property failed at step {}: {}\nminimal case: {:?}\nreplay: {}",
+                failure.step,
+                failure.message,
+                case,
+                serde_json::to_string(&failure.game.replay()).unwrap()
+            );
+        }
+    }
+}
+
+fn valid_state(_: &Game, after: &Game) -> Result<(), String> {
+    let violations = after.validate();
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(violations
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<String>>()
+            .join(", "))
+    }
+}
+
+/// Score cards from the rules directly rather than through
+/// `GameOptions::score`, so the game's scoring isn't checked against itself.
+fn reference_score(options: &GameOptions, cards: &[Card]) -> isize {
+    let mut by_expedition: BTreeMap<Expedition, Vec<Value>> = BTreeMap::new();
+    for c in cards {
+        by_expedition.entry(c.expedition).or_default().push(c.value);
+    }
+    by_expedition
+        .values()
+        .map(|values| {
+            let mut sum: isize = 0;
+            let mut multiplier: isize = 1;
+            for v in values {
+                match *v {
+                    Value::Investment => multiplier += 1,
+                    Value::N(n) => sum += n as isize,
+                }
+            }
+            let bonus = if values.len() >= options.expedition_bonus_size {
+                options.expedition_bonus
+            } else {
+                0
+            };
+            (sum - options.expedition_cost) * multiplier + bonus
+        })
+        .sum()
+}
+
+/// When a round ends each player's round score must match scoring the
+/// expeditions they had.
+fn round_scores_match(before: &Game, after: &Game) -> Result<(), String> {
+    if after.round == before.round {
+        return Ok(());
+    }
+    for p in 0..after.players {
+        let expected = reference_score(&after.options, &before.expeditions[p]);
+        let found = after.scores[p].last().cloned();
+        if found != Some(expected) {
+            return Err(format!(
+                "player {} scored {:?} for the round, expected {}",
+                p, found, expected
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Deal the hidden cards again, keeping revealed cards in their hands.
+fn redeal_hidden<R: Rng>(game: &Game, keep: Option<usize>, rng: &mut R) -> Game {
+    let mut redealt = game.clone();
+    let mut pool: Vec<Card> = redealt.deck.drain(..).collect();
+    let mut sizes: Vec<usize> = vec![];
+    for (p, hand) in redealt.hands.iter_mut().enumerate() {
+        let mut revealed = game.revealed.get(p).cloned().unwrap_or_default();
+        let mut kept: Vec<Card> = vec![];
+        for c in hand.drain(..) {
+            if keep == Some(p) {
+                kept.push(c);
+            } else if let Some(i) = revealed.iter().position(|&rc| rc == c) {
+                revealed.remove(i);
+                kept.push(c);
+            } else {
+                pool.push(c);
+            }
+        }
+        sizes.push(game.hands[p].len() - kept.len());
+        *hand = kept;
+    }
+    rng.shuffle(pool.as_mut_slice());
+    for (hand, size) in redealt.hands.iter_mut().zip(sizes) {
+        hand.extend(pool.drain(..size));
+    }
+    redealt.deck = pool;
+    redealt
+}
+
+/// Public state must not change when hidden cards are dealt differently, and
+/// a player's state must not depend on the other hands.
+fn no_hidden_leaks(_: &Game, after: &Game) -> Result<(), String> {
+    let mut rng = StdRng::from_seed(&[after.history.len()][..]);
+    let redealt = redeal_hidden(after, None, &mut rng);
+    if serde_json::to_value(after.pub_state()).unwrap()
+        != serde_json::to_value(redealt.pub_state()).unwrap()
+    {
+        return Err("public state depends on hidden cards".to_string());
+    }
+    for p in 0..after.players {
+        let redealt = redeal_hidden(after, Some(p), &mut rng);
+        if serde_json::to_value(after.player_state(p)).unwrap()
+            != serde_json::to_value(redealt.player_state(p)).unwrap()
+        {
+            return Err(format!(
+                "player {}'s state depends on other players' hidden cards",
+                p
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn random_games_hold_invariants() {
+    check(&[valid_state, round_scores_match, no_hidden_leaks]);
+}
+
+#[test]
+fn reference_score_works() {
+    let options = GameOptions::default();
+    let mut cards: Vec<Card> = vec![(Expedition::Red, Value::Investment).into()];
+    for n in 2..11 {
+        cards.push((Expedition::Red, Value::N(n)).into());
+    }
+    cards.push((Expedition::Blue, Value::N(5)).into());
+    // Red: (54 - 20) * 2 + 20, blue: 5 - 20.
+    assert_eq!(88 - 15, reference_score(&options, &cards));
+    assert_eq!(0, reference_score(&options, &[]));
+}
+
+#[test]
+fn shrinking_works() {
+    // A property that fails once anyone plays a card.
+    fn no_plays(_: &Game, after: &Game) -> Result<(), String> {
+        if after.expeditions.iter().all(|e| e.is_empty()) {
+            Ok(())
+        } else {
+            Err("a card was played".to_string())
+        }
+    }
+    let case = Case {
+        players: 2,
+        seed: 1,
+        choices: vec![5; 100],
+    };
+    assert!(run(&case, &[no_plays]).is_err());
+    let (shrunk, failure) = shrink(case, &[no_plays]);
+    // Playing the first legal move is the smallest failing case.
+    assert_eq!(vec![0], shrunk.choices);
+    assert_eq!(0, failure.step);
+}
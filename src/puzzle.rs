@@ -0,0 +1,230 @@
+use serde_derive::{Deserialize, Serialize};
+
+use brdgme_game::errors::GameError;
+use brdgme_game::Gamer;
+
+use crate::bot::{Bot, HeuristicBot};
+use crate::position::Position;
+use crate::Game;
+
+use std::fmt;
+
+/// What the player must achieve by the end of the puzzle's round.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Goal {
+    /// Finish the round at least this many points ahead of every opponent.
+    WinRoundBy(isize),
+    /// Score at least this many points in the round, set to the best possible
+    /// score for "maximise your score" puzzles.
+    ScoreAtLeast(isize),
+}
+
+impl fmt::Display for Goal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Goal::WinRoundBy(n) => write!(f, "finish the round at least {} points ahead", n),
+            Goal::ScoreAtLeast(n) => write!(f, "score at least {} points this round", n),
+        }
+    }
+}
+
+/// A position to play from with a goal, opponents are played by the
+/// heuristic bot so puzzles are deterministic.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Puzzle {
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    pub position: Position,
+    /// The player solving the puzzle.
+    pub player: usize,
+    pub goal: Goal,
+    /// Show the order of the deck to the player.
+    #[serde(default)]
+    pub show_deck: bool,
+}
+
+/// How a finished puzzle went, with the player's round score and how far
+/// ahead of the best opponent they finished.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    Solved { score: isize, margin: isize },
+    Failed { score: isize, margin: isize },
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Outcome::Solved { score, margin } => write!(
+                f,
+                "Solved! You scored {} points, {} ahead of your opponents",
+                score, margin
+            ),
+            Outcome::Failed { score, margin } => write!(
+                f,
+                "Not quite, you scored {} points, {} ahead of your opponents",
+                score, margin
+            ),
+        }
+    }
+}
+
+impl Puzzle {
+    /// The game at the start of the puzzle, with opponents having moved until
+    /// it's the player's turn.
+    pub fn start(&self) -> Result<Game, GameError> {
+        if self.player >= self.position.players {
+            return Err(GameError::invalid_input(format!(
+                "player {} isn't in the puzzle",
+                self.player
+            )));
+        }
+        let mut game = Game::from_position(&self.position)?;
+        self.play_opponents(&mut game)?;
+        Ok(game)
+    }
+
+    /// Run the player's command, then let the opponents move.
+    pub fn command(&self, game: &mut Game, input: &str) -> Result<(), GameError> {
+        if self.is_over(game) {
+            return Err(GameError::invalid_input("the puzzle is over"));
+        }
+        game.command(self.player, input, &[])?;
+        self.play_opponents(game)
+    }
+
+    fn play_opponents(&self, game: &mut Game) -> Result<(), GameError> {
+        while !self.is_over(game) && game.current_player != self.player {
+            let p = game.current_player;
+            let command = HeuristicBot.choose(&game.player_state(p));
+            game.apply_command(p, command)?;
+        }
+        Ok(())
+    }
+
+    /// Whether the puzzle's round has ended.
+    pub fn is_over(&self, game: &Game) -> bool {
+        game.round != self.position.round || game.is_finished()
+    }
+
+    /// The outcome once the puzzle's round has ended.
+    pub fn outcome(&self, game: &Game) -> Option<Outcome> {
+        if !self.is_over(game) {
+            return None;
+        }
+        let round_score = |p: usize| -> isize {
+            game.scores
+                .get(p)
+                .and_then(|s| s.get(self.position.scores.get(p).map_or(0, |ps| ps.len())))
+                .cloned()
+                .unwrap_or(0)
+        };
+        let score = round_score(self.player);
+        let margin = score
+            - (0..game.players)
+                .filter(|&p| p != self.player)
+                .map(round_score)
+                .max()
+                .unwrap_or(0);
+        let solved = match self.goal {
+            Goal::WinRoundBy(n) => margin >= n,
+            Goal::ScoreAtLeast(n) => score >= n,
+        };
+        Some(if solved {
+            Outcome::Solved { score, margin }
+        } else {
+            Outcome::Failed { score, margin }
+        })
+    }
+
+    /// Play a sequence of commands from the start of the puzzle, returning the
+    /// outcome if the round ended.
+    pub fn check(&self, inputs: &[&str]) -> Result<Option<Outcome>, GameError> {
+        let mut game = self.start()?;
+        for input in inputs {
+            self.command(&mut game, input)?;
+        }
+        Ok(self.outcome(&game))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::Expedition;
+    use crate::options::GameOptions;
+    use crate::test_helpers::card;
+
+    /// The last card is about to be drawn, playing R10 onto R9 wins the round
+    /// by more than discarding it.
+    fn puzzle(goal: Goal) -> Puzzle {
+        let mut position = Position::new(2);
+        let options = GameOptions {
+            hand_size: 2,
+            ..GameOptions::default()
+        };
+        position.hands = vec![
+            vec![card(Expedition::Red, 10), card(Expedition::Green, 2)],
+            vec![card(Expedition::Blue, 2), card(Expedition::Blue, 3)],
+        ];
+        position.expeditions[0] = vec![
+            card(Expedition::Red, 7),
+            card(Expedition::Red, 8),
+            card(Expedition::Red, 9),
+        ];
+        let mut rest = options.initial_deck();
+        for c in position.hands.iter().chain(position.expeditions.iter()).flatten() {
+            let i = rest.iter().position(|rc| rc == c).unwrap();
+            rest.remove(i);
+        }
+        // Bury everything else in the discards so the deck has one card.
+        position.deck = vec![rest.pop().unwrap()];
+        for c in rest {
+            position.discards.entry(c.expedition).or_default().push(c);
+        }
+        position.options = Some(options);
+        Puzzle {
+            title: "Last draw".to_string(),
+            description: String::new(),
+            position,
+            player: 0,
+            goal,
+            show_deck: true,
+        }
+    }
+
+    #[test]
+    fn check_works() {
+        let p = puzzle(Goal::ScoreAtLeast(14));
+        assert_eq!(
+            Some(Outcome::Solved {
+                score: 14,
+                margin: 14,
+            }),
+            p.check(&["play r10", "draw"]).unwrap()
+        );
+        assert_eq!(
+            Some(Outcome::Failed {
+                score: 4,
+                margin: 4,
+            }),
+            p.check(&["discard r10 draw"]).unwrap()
+        );
+        assert_eq!(None, p.check(&["play r10"]).unwrap());
+        assert!(p.check(&["play b2"]).is_err());
+        let p = puzzle(Goal::WinRoundBy(5));
+        assert!(matches!(
+            p.check(&["play r10, draw"]).unwrap(),
+            Some(Outcome::Solved { .. })
+        ));
+    }
+
+    #[test]
+    fn puzzle_is_over_after_the_round() {
+        let p = puzzle(Goal::WinRoundBy(0));
+        let mut game = p.start().unwrap();
+        p.command(&mut game, "play r10 draw").unwrap();
+        assert!(p.is_over(&game));
+        assert!(p.command(&mut game, "discard g2").is_err());
+    }
+}
@@ -1,7 +1,9 @@
 use std::cmp;
+use std::collections::HashMap;
 
-use crate::{next_player, PlayerState, PubState, MAX_PLAYERS, ROUNDS, START_ROUND};
-use crate::card::{by_expedition, expeditions, Card};
+use crate::{next_player, PlayerState, PubState, START_ROUND};
+use crate::card::{by_expedition, Card, Expedition};
+use crate::solo::rival_node;
 
 use brdgme_color::GREY;
 use brdgme_game::Renderer;
@@ -16,7 +18,12 @@ const OPPONENT_SPACER: &'static str = "       ";
 const SCORE_SPACER: &'static str = "  ";
 const EMPTY_CARD_PILE: &'static str = "--";
 
-fn render(pub_state: &PubState, player: Option<usize>, hand: Option<&[Card]>) -> Vec<N> {
+fn render(
+    pub_state: &PubState,
+    player: Option<usize>,
+    hand: Option<&[Card]>,
+    unseen: Option<&HashMap<Expedition, Vec<Card>>>,
+) -> Vec<N> {
     let mut layout: Vec<Row> = vec![];
     if !pub_state.is_finished {
         layout.extend(vec![
@@ -27,7 +34,7 @@ fn render(pub_state: &PubState, player: Option<usize>, hand: Option<&[Card]>) ->
                         N::text("Round "),
                         N::Bold(vec![N::text(format!("{}", pub_state.round))]),
                         N::text(" of "),
-                        N::Bold(vec![N::text(format!("{}", super::ROUNDS))]),
+                        N::Bold(vec![N::text(format!("{}", pub_state.options.rounds))]),
                     ],
                 ),
             ],
@@ -53,14 +60,83 @@ fn render(pub_state: &PubState, player: Option<usize>, hand: Option<&[Card]>) ->
             vec![(A::Center, render_hand(h))],
         ]);
     }
-    // Scores
+    if let Some(u) = unseen {
+        if pub_state.options.card_tracker {
+            layout.append(&mut vec![
+                vec![],
+                vec![
+                    (
+                        A::Center,
+                        vec![N::Fg(GREY.into(), vec![N::text("Unseen cards")])],
+                    ),
+                ],
+            ]);
+            for e in pub_state.options.expeditions() {
+                let cards = u.get(&e).map(|c| c.as_slice()).unwrap_or(&[]);
+                layout.push(vec![
+                    (
+                        A::Center,
+                        if cards.is_empty() {
+                            vec![N::Fg(e.color().into(), vec![N::text(EMPTY_CARD_PILE)])]
+                        } else {
+                            render_hand(cards)
+                        },
+                    ),
+                ]);
+            }
+        }
+    }
     let persp = match player {
         Some(p) if p < pub_state.players => p,
         _ => 0,
     };
+    // Open information
+    if let Some(ref hands) = pub_state.hands {
+        for p_offset in 0..pub_state.players {
+            let p = (persp + p_offset) % pub_state.players;
+            if Some(p) == player {
+                continue;
+            }
+            layout.append(&mut vec![
+                vec![],
+                vec![
+                    (
+                        A::Center,
+                        vec![
+                            N::Player(p),
+                            N::Fg(GREY.into(), vec![N::text("'s hand")]),
+                        ],
+                    ),
+                ],
+                vec![(A::Center, render_hand(hands.get(p).map_or(&[], |h| h.as_slice())))],
+            ]);
+        }
+    }
+    if let Some(ref deck) = pub_state.deck {
+        layout.append(&mut vec![
+            vec![],
+            vec![
+                (
+                    A::Center,
+                    vec![N::Fg(GREY.into(), vec![N::text("Deck from the top")])],
+                ),
+            ],
+            vec![
+                (
+                    A::Center,
+                    if deck.is_empty() {
+                        vec![N::text(EMPTY_CARD_PILE)]
+                    } else {
+                        render_deck(deck)
+                    },
+                ),
+            ],
+        ]);
+    }
+    // Scores
     let mut scores: Vec<Row> = vec![];
     let mut header: Row = vec![(A::Left, vec![])];
-    for r in START_ROUND..(START_ROUND + ROUNDS) {
+    for r in START_ROUND..(START_ROUND + pub_state.options.rounds) {
         header.extend(vec![
             (A::Left, vec![N::text(SCORE_SPACER)]),
             (
@@ -77,7 +153,7 @@ fn render(pub_state: &PubState, player: Option<usize>, hand: Option<&[Card]>) ->
     for p_offset in 0..pub_state.players {
         let p = (persp + p_offset) % pub_state.players;
         let mut score_row: Row = vec![(A::Right, vec![N::Player(p)])];
-        for r in 0..ROUNDS {
+        for r in 0..pub_state.options.rounds {
             score_row.extend(vec![
                 (A::Left, vec![]),
                 (
@@ -104,6 +180,32 @@ fn render(pub_state: &PubState, player: Option<usize>, hand: Option<&[Card]>) ->
         ]);
         scores.push(score_row);
     }
+    if let Some(ref rival) = pub_state.rival {
+        let mut score_row: Row = vec![(A::Right, vec![rival_node()])];
+        for r in 0..pub_state.options.rounds {
+            score_row.extend(vec![
+                (A::Left, vec![]),
+                (
+                    A::Center,
+                    vec![N::text(
+                        rival
+                            .scores
+                            .get(r)
+                            .map(|rs| format!("{}", rs))
+                            .unwrap_or_else(|| "".to_string()),
+                    )],
+                ),
+            ]);
+        }
+        score_row.extend(vec![
+            (A::Left, vec![]),
+            (
+                A::Center,
+                vec![N::text(format!("{}", rival.scores.iter().sum::<isize>()))],
+            ),
+        ]);
+        scores.push(score_row);
+    }
     layout.append(&mut vec![
         vec![],
         vec![
@@ -116,28 +218,43 @@ fn render(pub_state: &PubState, player: Option<usize>, hand: Option<&[Card]>) ->
 
 impl Renderer for PubState {
     fn render(&self) -> Vec<N> {
-        render(self, None, None)
+        render(self, None, None, None)
     }
 }
 
 impl Renderer for PlayerState {
     fn render(&self) -> Vec<N> {
-        render(&self.public, Some(self.player), Some(&self.hand))
+        render(
+            &self.public,
+            Some(self.player),
+            Some(&self.hand),
+            Some(&self.unseen),
+        )
     }
 }
 
 impl PubState {
     fn render_tableau(&self, player: Option<usize>) -> Vec<N> {
-        let p = player.unwrap_or(0) % MAX_PLAYERS;
+        let p = player.unwrap_or(0) % cmp::max(self.players, 1);
+        let exps = self.options.expeditions();
         let mut layout: Vec<N> = vec![];
         let mut rows: Vec<Row> = vec![];
 
         // Top half
         match self.players {
+            1 => {
+                // Solo, the rival is rendered as the opponent.
+                let mut top = match self.rival {
+                    Some(ref r) => render_tableau_cards(&r.expeditions, &rival_node(), &exps),
+                    None => vec![],
+                };
+                top.reverse();
+                rows.append(&mut top);
+            }
             2 => {
                 // Two players, we just put the top in the main table.
                 let mut top = match self.expeditions.get(next_player(p, self.players)) {
-                    Some(e) => render_tableau_cards(e, &N::Player(next_player(p, self.players))),
+                    Some(e) => render_tableau_cards(e, &N::Player(next_player(p, self.players)), &exps),
                     None => vec![],
                 };
                 top.reverse();
@@ -152,7 +269,7 @@ impl PubState {
                 for opp_offset in 1..self.players {
                     let opp = (p + opp_offset) % self.players;
                     let mut opp_tableau = match self.expeditions.get(opp) {
-                        Some(e) => render_tableau_cards(e, &N::Player(opp)),
+                        Some(e) => render_tableau_cards(e, &N::Player(opp), &exps),
                         None => vec![],
                     };
                     let height = opp_tableau.len();
@@ -199,7 +316,7 @@ impl PubState {
         let mut discards: Row = vec![
             (A::Right, vec![N::Fg(GREY.into(), vec![N::text("Discard")])]),
         ];
-        for (i, &e) in expeditions().iter().enumerate() {
+        for (i, &e) in exps.iter().enumerate() {
             // Column spacing
             discards.push((
                 A::Left,
@@ -239,6 +356,34 @@ impl PubState {
 
         rows.push(discards);
 
+        // Discard pile depth
+        let mut depths: Row = vec![(A::Left, vec![])];
+        for (i, e) in exps.iter().enumerate() {
+            depths.push((
+                A::Left,
+                vec![
+                    N::text(if i == 0 {
+                        TABLEAU_HEADER_SPACER
+                    } else {
+                        EXP_SPACER
+                    }),
+                ],
+            ));
+            depths.push((
+                A::Center,
+                vec![
+                    N::Fg(
+                        GREY.into(),
+                        vec![N::text(match self.discard_sizes.get(e) {
+                            Some(&n) if n > 0 => format!("{}", n),
+                            _ => "".to_string(),
+                        })],
+                    ),
+                ],
+            ));
+        }
+        rows.push(depths);
+
         // Blank row
         rows.push(vec![]);
         if self.players > 2 {
@@ -248,7 +393,7 @@ impl PubState {
 
         // Bottom half
         if let Some(e) = self.expeditions.get(p) {
-            rows.append(&mut render_tableau_cards(e, &N::Player(p)));
+            rows.append(&mut render_tableau_cards(e, &N::Player(p), &exps));
         }
         layout.push(N::Table(rows));
         layout
@@ -262,12 +407,12 @@ impl PubState {
     }
 }
 
-fn render_tableau_cards(cards: &[Card], header: &N) -> Vec<Row> {
+fn render_tableau_cards(cards: &[Card], header: &N, expeditions: &[Expedition]) -> Vec<Row> {
     let mut rows: Vec<Row> = vec![];
     let by_exp = by_expedition(cards);
     let mut largest: usize = 1;
-    for e in expeditions() {
-        largest = cmp::max(largest, by_exp.get(&e).unwrap_or(&vec![]).len());
+    for e in expeditions.iter() {
+        largest = cmp::max(largest, by_exp.get(e).unwrap_or(&vec![]).len());
     }
     for row_i in 0..largest {
         let mut row: Row = vec![
@@ -277,7 +422,7 @@ fn render_tableau_cards(cards: &[Card], header: &N) -> Vec<Row> {
                 (A::Left, vec![])
             },
         ];
-        for (i, &e) in expeditions().iter().enumerate() {
+        for (i, &e) in expeditions.iter().enumerate() {
             // Column spacing
             row.push((
                 A::Left,
@@ -326,6 +471,18 @@ fn render_hand(cards: &[Card]) -> Vec<N> {
     output
 }
 
+/// Render cards in the order given rather than sorting them.
+fn render_deck(cards: &[Card]) -> Vec<N> {
+    let mut output: Vec<N> = vec![];
+    for c in cards {
+        if !output.is_empty() {
+            output.push(N::text(" "));
+        }
+        output.push(card(c));
+    }
+    output
+}
+
 pub fn card(c: &Card) -> N {
     N::Bold(vec![
         N::Fg(c.expedition.color().into(), vec![N::text(c.to_string())]),
@@ -342,3 +499,81 @@ pub fn comma_cards(cards: &[Card]) -> Vec<N> {
     }
     output
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Game;
+    use brdgme_game::Gamer;
+
+    fn players_in(nodes: &[N], found: &mut Vec<usize>) {
+        for n in nodes {
+            match *n {
+                N::Player(p) => found.push(p),
+                N::Bold(ref c) | N::Fg(_, ref c) | N::Bg(_, ref c) | N::Group(ref c) => {
+                    players_in(c, found)
+                }
+                N::Align(_, _, ref c) => players_in(c, found),
+                N::Table(ref rows) => for row in rows {
+                    for (_, c) in row {
+                        players_in(c, found);
+                    }
+                },
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn render_tableau_2p_works() {
+        let game = Game::new_with_seed(2, 1).unwrap().0;
+        let tableau = game.pub_state().render_tableau(Some(1));
+        assert_eq!(1, tableau.len());
+        let mut found = vec![];
+        players_in(&tableau, &mut found);
+        // The opponent is rendered above the player.
+        assert_eq!(vec![0, 1], found);
+    }
+
+    #[test]
+    fn render_tableau_3p_works() {
+        let game = Game::new_with_seed(3, 1).unwrap().0;
+        for p in 0..3 {
+            let tableau = game.pub_state().render_tableau(Some(p));
+            // Opponents are rendered side by side in their own table.
+            assert_eq!(2, tableau.len());
+            let mut opponents = vec![];
+            players_in(&tableau[..1], &mut opponents);
+            assert_eq!(vec![(p + 1) % 3, (p + 2) % 3], opponents);
+            let mut own = vec![];
+            players_in(&tableau[1..], &mut own);
+            assert_eq!(vec![p], own);
+            assert!(!game.player_state(p).render().is_empty());
+        }
+        assert!(!game.pub_state().render().is_empty());
+    }
+
+    #[test]
+    fn render_tableau_solo_works() {
+        let mut game = Game::new_with_seed(1, 1).unwrap().0;
+        game.rival.as_mut().unwrap().expeditions =
+            vec![(Expedition::Red, crate::card::Value::N(3)).into()];
+        let tableau = game.pub_state().render_tableau(Some(0));
+        let mut found = vec![];
+        players_in(&tableau, &mut found);
+        assert_eq!(vec![0], found);
+        assert!(!game.player_state(0).render().is_empty());
+    }
+
+    #[test]
+    fn render_open_information_works() {
+        let mut game = Game::new_with_seed(3, 1).unwrap().0;
+        let mut closed = vec![];
+        players_in(&game.player_state(1).render(), &mut closed);
+        game.options.open_information = true;
+        let mut open = vec![];
+        players_in(&game.player_state(1).render(), &mut open);
+        // Each opponent's hand gets a heading.
+        assert_eq!(closed.len() + 2, open.len());
+    }
+}
@@ -0,0 +1,92 @@
+use serde_derive::{Deserialize, Serialize};
+
+use brdgme_game::errors::GameError;
+
+use crate::command::Command;
+use crate::options::GameOptions;
+use crate::position::Position;
+use crate::Game;
+
+/// Everything needed to rebuild a game move by move, the seed fixes the deal
+/// for every round so only the commands need to be stored.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub players: usize,
+    pub seed: u64,
+    pub options: GameOptions,
+    pub commands: Vec<(usize, Command)>,
+    /// The position to start from for games built from a position.
+    #[serde(default)]
+    pub start: Option<Position>,
+}
+
+impl Replay {
+    /// Rebuild the game, passing the game before any commands and after each
+    /// command to `visit`.
+    fn play<F: FnMut(&Game)>(&self, mut visit: F) -> Result<Game, GameError> {
+        let mut game = match self.start {
+            Some(ref position) => Game::from_position(position)?,
+            None => Game::new_with_options(self.players, self.options.clone(), self.seed)?.0,
+        };
+        visit(&game);
+        for &(player, command) in &self.commands {
+            game.apply_command(player, command)?;
+            visit(&game);
+        }
+        Ok(game)
+    }
+
+    /// The game before any commands, followed by the game after each command.
+    pub fn states(&self) -> Result<Vec<Game>, GameError> {
+        let mut states = vec![];
+        self.play(|game| states.push(game.clone()))?;
+        Ok(states)
+    }
+}
+
+impl Game {
+    /// The replay of the game so far, undone moves are left out.
+    pub fn replay(&self) -> Replay {
+        Replay {
+            players: self.players,
+            seed: self.seed,
+            options: self.options.clone(),
+            commands: self.round_commands().into_iter().flatten().collect(),
+            start: self.start.clone(),
+        }
+    }
+
+    pub fn from_replay(replay: &Replay) -> Result<Game, GameError> {
+        replay.play(|_| {})
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::{Bot, HeuristicBot};
+    use brdgme_game::Gamer;
+
+    #[test]
+    fn replay_works() {
+        let mut game = Game::new_with_seed(2, 3).unwrap().0;
+        while !game.is_finished() {
+            let p = game.current_player;
+            let command = HeuristicBot.choose(&game.player_state(p));
+            game.apply_command(p, command).unwrap();
+        }
+        let replay = game.replay();
+        assert_eq!(game, Game::from_replay(&replay).unwrap());
+        let states = replay.states().unwrap();
+        assert_eq!(replay.commands.len() + 1, states.len());
+        assert_eq!(Some(&game), states.last());
+    }
+
+    #[test]
+    fn from_replay_rejects_invalid_commands() {
+        let game = Game::new_with_seed(2, 3).unwrap().0;
+        let mut replay = game.replay();
+        replay.commands.push((1, Command::Draw));
+        assert!(Game::from_replay(&replay).is_err());
+    }
+}
@@ -0,0 +1,121 @@
+use serde_derive::{Deserialize, Serialize};
+
+use std::fmt;
+
+use crate::card::{Card, Expedition, Value};
+use crate::options::GameOptions;
+
+/// How an expedition's score is made up.
+#[derive(PartialEq, Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ScoreBreakdown {
+    pub expedition: Expedition,
+    /// Number of cards in the expedition, including investments.
+    pub cards: usize,
+    /// Sum of the numbered cards.
+    pub sum: isize,
+    pub cost: isize,
+    /// One more than the number of investments.
+    pub multiplier: isize,
+    /// Bonus for playing enough cards, zero if not reached.
+    pub bonus: isize,
+    pub subtotal: isize,
+}
+
+impl fmt::Display for ScoreBreakdown {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.multiplier > 1 {
+            write!(f, "({} - {}) × {}", self.sum, self.cost, self.multiplier)?;
+        } else {
+            write!(f, "{} - {}", self.sum, self.cost)?;
+        }
+        if self.bonus != 0 {
+            write!(f, " + {}", self.bonus)?;
+        }
+        write!(f, " = {}", self.subtotal)
+    }
+}
+
+impl GameOptions {
+    /// The score breakdown for a single expedition, or `None` if it hasn't
+    /// been started.
+    pub fn expedition_breakdown(&self, cards: &[Card], e: Expedition) -> Option<ScoreBreakdown> {
+        let mut count: usize = 0;
+        let mut inv: isize = 0;
+        let mut sum: isize = 0;
+        for c in cards.iter().filter(|c| c.expedition == e) {
+            count += 1;
+            match c.value {
+                Value::Investment => inv += 1,
+                Value::N(n) => sum += n as isize,
+            }
+        }
+        if count == 0 {
+            return None;
+        }
+        let multiplier = inv + 1;
+        let bonus = if count >= self.expedition_bonus_size {
+            self.expedition_bonus
+        } else {
+            0
+        };
+        Some(ScoreBreakdown {
+            expedition: e,
+            cards: count,
+            sum,
+            cost: self.expedition_cost,
+            multiplier,
+            bonus,
+            subtotal: (sum - self.expedition_cost) * multiplier + bonus,
+        })
+    }
+
+    /// Score breakdowns for each started expedition.
+    pub fn score_breakdown(&self, cards: &[Card]) -> Vec<ScoreBreakdown> {
+        self.expeditions()
+            .into_iter()
+            .filter_map(|e| self.expedition_breakdown(cards, e))
+            .collect()
+    }
+
+    pub fn score(&self, cards: &[Card]) -> isize {
+        self.expeditions().iter().fold(0, |acc, &e| {
+            acc + self.expedition_breakdown(cards, e)
+                .map(|b| b.subtotal)
+                .unwrap_or(0)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn score_breakdown_works() {
+        let mut cards: Vec<Card> = vec![
+            (Expedition::Green, Value::Investment).into(),
+            (Expedition::Red, Value::N(4)).into(),
+        ];
+        cards.extend(
+            (3..10).map(|v| -> Card { (Expedition::Green, Value::N(v)).into() }),
+        );
+        let opts = GameOptions::default();
+        let breakdown = opts.score_breakdown(&cards);
+        assert_eq!(2, breakdown.len());
+        assert_eq!(
+            ScoreBreakdown {
+                expedition: Expedition::Red,
+                cards: 1,
+                sum: 4,
+                cost: 20,
+                multiplier: 1,
+                bonus: 0,
+                subtotal: -16,
+            },
+            breakdown[0]
+        );
+        assert_eq!("4 - 20 = -16", breakdown[0].to_string());
+        assert_eq!("(42 - 20) × 2 + 20 = 64", breakdown[1].to_string());
+        assert_eq!(48, opts.score(&cards));
+    }
+}
@@ -0,0 +1,220 @@
+//! Solo play against an automated rival. The rival follows a fixed rule set
+//! so a single player can practise:
+//!
+//! 1. At the start of each round, after your hand is dealt, half of the
+//!    remaining deck is set aside face down as the rival's pile.
+//! 2. After each of your turns, the rival flips the top card of its pile.
+//! 3. An investment is played if the rival hasn't played a number to that
+//!    expedition yet.
+//! 4. A number is played if it's higher than every number the rival has
+//!    played to that expedition, and the expedition is either already started
+//!    or the number is 5 or lower.
+//! 5. Any other card is discarded on top of its discard pile, where you may
+//!    take it.
+//!
+//! The rival's expeditions are scored like yours at the end of each round.
+
+use serde_derive::{Deserialize, Serialize};
+
+use brdgme_game::Log;
+use brdgme_markup::Node as N;
+
+use crate::card::{Card, Value};
+use crate::render;
+use crate::Game;
+
+/// The highest number the rival will start a new expedition with.
+pub const RIVAL_MAX_START: usize = 5;
+
+/// The automated opponent in solo games, which plays by flipping cards from
+/// its own pile.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Rival {
+    /// The rival's face down pile, flipped from the top.
+    pub pile: Vec<Card>,
+    pub expeditions: Vec<Card>,
+    pub scores: Vec<isize>,
+}
+
+/// What everyone can see of the rival.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct RivalState {
+    pub pile_remaining: usize,
+    pub expeditions: Vec<Card>,
+    pub scores: Vec<isize>,
+}
+
+impl Rival {
+    pub fn state(&self) -> RivalState {
+        RivalState {
+            pile_remaining: self.pile.len(),
+            expeditions: self.expeditions.clone(),
+            scores: self.scores.clone(),
+        }
+    }
+
+    pub fn score(&self) -> isize {
+        self.scores.iter().sum()
+    }
+
+    /// Whether the rival's rules play the card rather than discard it.
+    pub fn plays(&self, c: Card) -> bool {
+        let mut started = false;
+        let mut highest: Option<usize> = None;
+        for ec in self.expeditions.iter().filter(|ec| ec.expedition == c.expedition) {
+            started = true;
+            if let Value::N(n) = ec.value {
+                highest = Some(highest.map_or(n, |h| h.max(n)));
+            }
+        }
+        match (c.value, highest) {
+            (Value::Investment, None) => true,
+            (Value::Investment, Some(_)) => false,
+            (Value::N(n), Some(h)) => n > h,
+            (Value::N(n), None) => started || n <= RIVAL_MAX_START,
+        }
+    }
+}
+
+/// The rival's name in logs and the tableau.
+pub(crate) fn rival_node() -> N {
+    N::Bold(vec![N::text("Rival")])
+}
+
+impl Game {
+    /// Set aside the rival's pile for a new round.
+    pub(crate) fn deal_rival(&mut self) {
+        let deck_len = self.deck.len();
+        if let Some(ref mut rival) = self.rival {
+            rival.pile = self.deck.split_off(deck_len - deck_len / 2);
+            rival.expeditions = vec![];
+        }
+    }
+
+    /// Flip the top card of the rival's pile and follow the rules for it.
+    pub(crate) fn rival_turn(&mut self) -> Vec<Log> {
+        let rival = match self.rival {
+            Some(ref mut rival) if !rival.pile.is_empty() => rival,
+            _ => return vec![],
+        };
+        let c = rival.pile.remove(0);
+        if rival.plays(c) {
+            rival.expeditions.push(c);
+            vec![Log::public(vec![rival_node(), N::text(" played "), render::card(&c)])]
+        } else {
+            self.discards.push(c);
+            vec![Log::public(vec![rival_node(), N::text(" discarded "), render::card(&c)])]
+        }
+    }
+
+    /// Score the rival's expeditions at the end of a round.
+    pub(crate) fn score_rival(&mut self) -> Vec<Log> {
+        let options = &self.options;
+        let rival = match self.rival {
+            Some(ref mut rival) => rival,
+            None => return vec![],
+        };
+        let round_score = options.score(&rival.expeditions);
+        rival.scores.push(round_score);
+        vec![Log::public(vec![
+            rival_node(),
+            N::text(" scored "),
+            N::Bold(vec![N::text(format!("{}", round_score))]),
+            N::text(" points, now on "),
+            N::Bold(vec![N::text(format!("{}", rival.score()))]),
+        ])]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::{Bot, HeuristicBot};
+    use crate::card::Expedition;
+    use crate::command::Command;
+    use brdgme_game::Gamer;
+
+    #[test]
+    fn rival_plays_works() {
+        let mut rival = Rival::default();
+        let c = |e, v| -> Card { (e, Value::N(v)).into() };
+        let x = |e| -> Card { (e, Value::Investment).into() };
+        assert!(rival.plays(x(Expedition::Red)));
+        assert!(rival.plays(c(Expedition::Red, 5)));
+        assert!(!rival.plays(c(Expedition::Red, 6)));
+        rival.expeditions = vec![x(Expedition::Red)];
+        assert!(rival.plays(x(Expedition::Red)));
+        assert!(rival.plays(c(Expedition::Red, 9)));
+        rival.expeditions.push(c(Expedition::Red, 4));
+        assert!(!rival.plays(x(Expedition::Red)));
+        assert!(!rival.plays(c(Expedition::Red, 3)));
+        assert!(rival.plays(c(Expedition::Red, 8)));
+    }
+
+    #[test]
+    fn solo_game_works() {
+        let mut game = Game::new_with_seed(1, 3).unwrap().0;
+        let rival_pile = game.rival.as_ref().unwrap().pile.len();
+        assert_eq!(game.deck.len(), rival_pile);
+        assert_eq!(Vec::<crate::validate::Violation>::new(), game.validate());
+        let c = game.hands[0][0];
+        game.discard(0, c).unwrap();
+        game.draw(0).unwrap();
+        let rival = game.rival.as_ref().unwrap();
+        assert_eq!(rival_pile - 1, rival.pile.len());
+        assert_eq!(0, game.current_player);
+        while !game.is_finished() {
+            let command = HeuristicBot.choose(&game.player_state(0));
+            game.apply_command(0, command).unwrap();
+            assert_eq!(Vec::<crate::validate::Violation>::new(), game.validate());
+        }
+        let rival = game.rival.as_ref().unwrap();
+        assert_eq!(game.options.rounds, rival.scores.len());
+        assert_eq!(rival.scores, game.pub_state().rival.unwrap().scores);
+    }
+
+    #[test]
+    fn solo_takes_cant_be_undone() {
+        let mut game = Game::new_with_seed(1, 3).unwrap().0;
+        let c = game.hands[0][0];
+        game.discard(0, c).unwrap();
+        game.draw(0).unwrap();
+        // Discard something else so there is a pile to take from.
+        let other = *game.hands[0]
+            .iter()
+            .find(|oc| oc.expedition != c.expedition)
+            .unwrap();
+        game.discard(0, other).unwrap();
+        let response = game.command(0, &format!("take {}", c.expedition), &[]);
+        assert!(!response.unwrap().can_undo);
+        assert!(game.undo(0).is_err());
+        assert_eq!(Some(Command::Take(c.expedition)), game.history.last().map(|m| m.command));
+    }
+
+    #[test]
+    fn solo_undo_across_turns_works() {
+        let mut game = Game::new_with_seed(1, 3).unwrap().0;
+        // Without a rival pile takes can be undone, even after undoing the
+        // next turn's discard.
+        let pile = game.rival.as_mut().unwrap().pile.split_off(0);
+        game.deck.extend(pile);
+        let other_than = |game: &Game, e: Expedition| -> Card {
+            *game.hands[0].iter().find(|c| c.expedition != e).unwrap()
+        };
+        let first = game.hands[0][0];
+        game.discard(0, first).unwrap();
+        game.draw(0).unwrap();
+        let second = other_than(&game, first.expedition);
+        game.discard(0, second).unwrap();
+        game.take(0, first.expedition).unwrap();
+        let third = other_than(&game, second.expedition);
+        game.discard(0, third).unwrap();
+        game.take(0, second.expedition).unwrap();
+        assert_eq!(vec![first, second], game.revealed[0]);
+        game.discard(0, first).unwrap();
+        game.undo(0).unwrap();
+        game.undo(0).unwrap();
+        assert_eq!(vec![first], game.revealed[0]);
+        assert_eq!(Vec::<crate::validate::Violation>::new(), game.validate());
+    }
+}
@@ -0,0 +1,561 @@
+use brdgme_game::errors::GameError;
+
+use std::cmp;
+use std::fmt;
+
+use crate::card::{Card, Expedition};
+use crate::command::Command;
+use crate::solo::Rival;
+use crate::{Game, PlayerState, Stats};
+
+/// The largest deck the solver will enumerate deals for by default.
+const DEFAULT_MAX_DECK: usize = 2;
+/// Turns searched before scoring the tableaus as they are, as players can
+/// keep taking discards and never end the round.
+const DEFAULT_MAX_TURNS: usize = 6;
+
+/// An endgame solver for two player games, using expectimax over the cards
+/// the player can't see.
+///
+/// Each way of dealing the opponent's unknown cards from the unseen cards is
+/// equally likely and enumerated at the root. Drawing from the deck is a
+/// chance node over the unseen cards left, as is uncovering a buried discard,
+/// as the order of buried discards isn't public without open discards. The
+/// player never chooses knowing the order of the deck, though after the root
+/// their choices can depend on the dealt opponent hand, which slightly
+/// favours the player.
+///
+/// The round is searched to its end, or for `max_turns` turns if players keep
+/// taking from the discards, after which the tableaus are scored as they are.
+#[derive(Debug, Clone, Copy)]
+pub struct Solver {
+    /// Only solve when there are this many cards or fewer in the deck, which
+    /// also limits the search with open information as the deck is searched
+    /// through to the end of the round.
+    pub max_deck: usize,
+    /// How many turns to search before scoring the tableaus as they are.
+    pub max_turns: usize,
+}
+
+impl Default for Solver {
+    fn default() -> Solver {
+        Solver {
+            max_deck: DEFAULT_MAX_DECK,
+            max_turns: DEFAULT_MAX_TURNS,
+        }
+    }
+}
+
+/// The expected outcome of a move, as the player's round score minus their
+/// opponent's.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MoveValue {
+    pub command: Command,
+    pub expected: f64,
+}
+
+/// How much worse a move is than the best move.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Regret {
+    pub command: Command,
+    pub best: Command,
+    pub loss: f64,
+}
+
+fn describe(command: Command) -> String {
+    match command {
+        Command::Play(c) => format!("playing {}", c),
+        Command::Discard(c) => format!("discarding {}", c),
+        Command::Take(e) => format!("taking from {}", e.name().to_lowercase()),
+        Command::Draw => "drawing".to_string(),
+    }
+}
+
+impl fmt::Display for Regret {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} instead of {} loses {:.1} points on average",
+            describe(self.command),
+            describe(self.best),
+            self.loss
+        )
+    }
+}
+
+/// How much a move loses against the best move, given solved values sorted
+/// best first.
+pub fn regret(values: &[MoveValue], command: Command) -> Option<Regret> {
+    let best = values.first()?;
+    values.iter().find(|v| v.command == command).map(|v| Regret {
+        command,
+        best: best.command,
+        loss: best.expected - v.expected,
+    })
+}
+
+impl Solver {
+    /// The expected value of every legal move for the player, best first.
+    pub fn solve(&self, state: &PlayerState) -> Result<Vec<MoveValue>, GameError> {
+        let public = &state.public;
+        if public.is_finished {
+            return Err(GameError::invalid_input("the game is over"));
+        }
+        if public.players != 2 {
+            return Err(GameError::invalid_input(
+                "the solver only supports two players",
+            ));
+        }
+        if public.current_player != state.player {
+            return Err(GameError::invalid_input("it's not the player's turn"));
+        }
+        let deck_known = public.deck.is_some();
+        if public.deck_remaining > self.max_deck {
+            return Err(GameError::invalid_input(format!(
+                "there are {} cards in the deck, the solver needs {} or fewer",
+                public.deck_remaining, self.max_deck
+            )));
+        }
+        let base = known_node(state);
+        let search = Search {
+            player: state.player,
+            deck_known,
+        };
+        let depth = self.max_turns * 2;
+        let moves = base.game.legal_moves(state.player);
+        let mut totals = vec![0.0; moves.len()];
+        let mut deals = vec![];
+        if search.deck_known {
+            // With open information there is nothing to deal.
+            deals.push(base);
+        } else {
+            let opponent = 1 - state.player;
+            let unseen: Vec<Card> = public
+                .options
+                .expeditions()
+                .iter()
+                .flat_map(|e| state.unseen.get(e).cloned().unwrap_or_default())
+                .collect();
+            for_each_deal(&unseen, public.deck_remaining, &mut |deck, rest| {
+                let mut node = base.clone();
+                node.game.deck = deck.to_vec();
+                node.game.hands[opponent].extend_from_slice(rest);
+                deals.push(node);
+            });
+        }
+        for node in &deals {
+            for (i, &m) in moves.iter().enumerate() {
+                totals[i] +=
+                    search.value_after(node, m, depth, f64::NEG_INFINITY, f64::INFINITY)?;
+            }
+        }
+        let deals = cmp::max(deals.len(), 1) as f64;
+        let mut values: Vec<MoveValue> = moves
+            .into_iter()
+            .zip(totals)
+            .map(|(command, total)| MoveValue {
+                command,
+                expected: total / deals,
+            })
+            .collect();
+        values.sort_by(|a, b| {
+            b.expected
+                .partial_cmp(&a.expected)
+                .unwrap_or(cmp::Ordering::Equal)
+        });
+        Ok(values)
+    }
+}
+
+/// A game in the search along with the cards buried in the discards, which
+/// are uncovered in an unknown order.
+#[derive(Clone)]
+pub(crate) struct Node {
+    pub(crate) game: Game,
+    pub(crate) buried: Vec<Card>,
+}
+
+struct Search {
+    player: usize,
+    /// Whether the order of the deck is known, otherwise draws are chance
+    /// nodes.
+    deck_known: bool,
+}
+
+impl Search {
+    /// The value of the game for the player after a move is made.
+    fn value_after(
+        &self,
+        node: &Node,
+        command: Command,
+        depth: usize,
+        alpha: f64,
+        beta: f64,
+    ) -> Result<f64, GameError> {
+        let game = &node.game;
+        if ends_round(game, command) {
+            // Drawing the last card scores the tableaus as they are.
+            return Ok(evaluate(game, self.player));
+        }
+        let depth = depth.saturating_sub(1);
+        let mut next = node.clone();
+        match command {
+            Command::Draw if !self.deck_known => {
+                // Any unseen card left could be on top of the deck.
+                chance(&game.deck, |c| {
+                    let mut next = node.clone();
+                    let i = next.game.deck.iter().position(|&dc| dc == c).unwrap_or(0);
+                    next.game.deck.swap(0, i);
+                    next.game.apply_command(game.current_player, command)?;
+                    self.value(&next, depth, f64::NEG_INFINITY, f64::INFINITY)
+                })
+            }
+            Command::Take(e) => {
+                next.game.apply_command(game.current_player, command)?;
+                if next.game.available_discard(e).is_some()
+                    || !next.buried.iter().any(|c| c.expedition == e)
+                {
+                    return self.value(&next, depth, alpha, beta);
+                }
+                // Any of the buried cards could be uncovered.
+                let buried: Vec<Card> = next
+                    .buried
+                    .iter()
+                    .filter(|c| c.expedition == e)
+                    .cloned()
+                    .collect();
+                chance(&buried, |c| {
+                    let mut uncovered = next.clone();
+                    remove_cards(&mut uncovered.buried, &[c]);
+                    uncovered.game.discards.push(c);
+                    self.value(&uncovered, depth, f64::NEG_INFINITY, f64::INFINITY)
+                })
+            }
+            _ => {
+                next.game.apply_command(game.current_player, command)?;
+                self.value(&next, depth, alpha, beta)
+            }
+        }
+    }
+
+    /// Alpha-beta search over the player's and opponent's choices. Children
+    /// of chance nodes are searched with a full window so their average is
+    /// exact.
+    fn value(
+        &self,
+        node: &Node,
+        depth: usize,
+        mut alpha: f64,
+        mut beta: f64,
+    ) -> Result<f64, GameError> {
+        let game = &node.game;
+        let moves = game.legal_moves(game.current_player);
+        if depth == 0 || moves.is_empty() {
+            return Ok(evaluate(game, self.player));
+        }
+        let maximising = game.current_player == self.player;
+        let mut best = if maximising {
+            f64::NEG_INFINITY
+        } else {
+            f64::INFINITY
+        };
+        for m in moves {
+            let v = self.value_after(node, m, depth, alpha, beta)?;
+            if maximising {
+                best = best.max(v);
+                alpha = alpha.max(v);
+            } else {
+                best = best.min(v);
+                beta = beta.min(v);
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+        Ok(best)
+    }
+}
+
+/// The average of `f` over `cards`, each equally likely.
+fn chance<F>(cards: &[Card], mut f: F) -> Result<f64, GameError>
+where
+    F: FnMut(Card) -> Result<f64, GameError>,
+{
+    let mut distinct = cards.to_vec();
+    distinct.sort();
+    distinct.dedup();
+    let mut total = 0.0;
+    for c in distinct {
+        let count = cards.iter().filter(|&&oc| oc == c).count();
+        total += count as f64 * f(c)?;
+    }
+    Ok(total / cmp::max(cards.len(), 1) as f64)
+}
+
+fn ends_round(game: &Game, command: Command) -> bool {
+    command == Command::Draw && game.deck.len() <= 1
+}
+
+fn evaluate(game: &Game, player: usize) -> f64 {
+    let mine = game.options.score(&game.expeditions[player]);
+    let best_opp = (0..game.players)
+        .filter(|&p| p != player)
+        .map(|p| game.options.score(&game.expeditions[p]))
+        .max()
+        .unwrap_or(0);
+    (mine - best_opp) as f64
+}
+
+/// Call `f` with every way of choosing `deck_size` cards from `cards` for the
+/// deck, in no particular order, along with the cards left over.
+fn for_each_deal<F: FnMut(&[Card], &[Card])>(cards: &[Card], deck_size: usize, f: &mut F) {
+    fn recurse<F: FnMut(&[Card], &[Card])>(
+        cards: &[Card],
+        from: usize,
+        deck: &mut Vec<Card>,
+        rest: &mut Vec<Card>,
+        deck_size: usize,
+        f: &mut F,
+    ) {
+        if deck.len() == deck_size || from == cards.len() {
+            let len = rest.len();
+            rest.extend_from_slice(&cards[from..]);
+            f(deck, rest);
+            rest.truncate(len);
+            return;
+        }
+        if cards.len() - from > deck_size - deck.len() {
+            rest.push(cards[from]);
+            recurse(cards, from + 1, deck, rest, deck_size, f);
+            rest.pop();
+        }
+        deck.push(cards[from]);
+        recurse(cards, from + 1, deck, rest, deck_size, f);
+        deck.pop();
+    }
+    recurse(cards, 0, &mut vec![], &mut vec![], deck_size, f);
+}
+
+fn remove_cards(cards: &mut Vec<Card>, remove: &[Card]) {
+    for c in remove {
+        if let Some(i) = cards.iter().position(|rc| rc == c) {
+            cards.remove(i);
+        }
+    }
+}
+
+/// The game as the player knows it. Unless playing with open information the
+/// deck is empty and only the revealed cards are in the opponent's hand, and
+/// unless playing with open discards only the top of each discard pile is in
+/// the discards with the rest buried. The rival's pile is left empty.
+pub(crate) fn known_node(state: &PlayerState) -> Node {
+    let public = &state.public;
+    let options = &public.options;
+    let tops: Vec<Card> = options
+        .expeditions()
+        .into_iter()
+        .filter_map(|e| public.discards.get(&e).map(|&v| (e, v).into()))
+        .collect();
+    // Everything the player has seen but can't currently see is buried in the
+    // discards.
+    let mut buried = options.initial_deck();
+    remove_cards(&mut buried, &state.hand);
+    for cards in public
+        .expeditions
+        .iter()
+        .chain(public.revealed.iter())
+        .chain(public.hands.iter().flatten())
+        .chain(public.deck.iter())
+        .chain(public.rival.iter().map(|r| &r.expeditions))
+    {
+        remove_cards(&mut buried, cards);
+    }
+    for cards in state.unseen.values() {
+        remove_cards(&mut buried, cards);
+    }
+    remove_cards(&mut buried, &tops);
+
+    // Keep the just discarded pile on top so it can't be taken back.
+    let mut expeditions: Vec<Expedition> = options.expeditions();
+    if let Some(de) = public.discarded_expedition {
+        expeditions.retain(|&e| e != de);
+        expeditions.push(de);
+    }
+    let mut discards: Vec<Card> = vec![];
+    for e in expeditions {
+        match public.discard_piles.as_ref().and_then(|dp| dp.get(&e)) {
+            Some(pile) => {
+                discards.extend(pile.iter().cloned());
+                buried.retain(|c| c.expedition != e);
+            }
+            None => discards.extend(tops.iter().filter(|c| c.expedition == e).cloned()),
+        }
+    }
+    let mut hands: Vec<Vec<Card>> = vec![];
+    for p in 0..public.players {
+        if let Some(ref h) = public.hands {
+            hands.push(h.get(p).cloned().unwrap_or_default());
+        } else if p == state.player {
+            hands.push(state.hand.clone());
+        } else {
+            hands.push(public.revealed.get(p).cloned().unwrap_or_default());
+        }
+    }
+    let game = Game {
+        players: public.players,
+        round: public.round,
+        phase: public.phase,
+        deck: public.deck.clone().unwrap_or_default(),
+        discards,
+        hands,
+        scores: public.scores.clone(),
+        expeditions: public.expeditions.clone(),
+        current_player: public.current_player,
+        discarded_expedition: public.discarded_expedition,
+        stats: vec![Stats::default(); public.players],
+        seed: 0,
+        options: options.clone(),
+        history: vec![],
+        past_rounds: vec![],
+        revealed: if public.revealed.len() == public.players {
+            public.revealed.clone()
+        } else {
+            vec![vec![]; public.players]
+        },
+        rival: public.rival.as_ref().map(|r| Rival {
+            pile: vec![],
+            expeditions: r.expeditions.clone(),
+            scores: r.scores.clone(),
+        }),
+        start: None,
+    };
+    Node { game, buried }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::Value;
+    use crate::options::GameOptions;
+    use brdgme_game::Gamer;
+
+    /// A small game near the end of the round so the solver is quick.
+    fn endgame() -> Game {
+        let options = GameOptions {
+            hand_size: 2,
+            ..GameOptions::default()
+        };
+        let mut game = Game::new_with_options(2, options, 1).unwrap().0;
+        game.deck = vec![
+            (Expedition::Blue, Value::N(9)).into(),
+            (Expedition::Yellow, Value::N(2)).into(),
+        ];
+        game.hands = vec![
+            vec![
+                (Expedition::Red, Value::N(10)).into(),
+                (Expedition::Green, Value::N(2)).into(),
+            ],
+            vec![
+                (Expedition::White, Value::N(2)).into(),
+                (Expedition::Blue, Value::N(2)).into(),
+            ],
+        ];
+        game.expeditions[0] = vec![(Expedition::Red, Value::N(9)).into()];
+        let mut used: Vec<Card> = game.deck.clone();
+        used.extend(game.expeditions[0].iter().cloned());
+        for h in &game.hands {
+            used.extend(h.iter().cloned());
+        }
+        let mut rest = game.options.initial_deck();
+        remove_cards(&mut rest, &used);
+        // Give every other card to the opponent's expeditions so the unseen
+        // cards are just the deck and the opponent's hand, and there are no
+        // discards to take.
+        rest.sort();
+        game.expeditions[1] = rest;
+        game.discards = vec![];
+        game
+    }
+
+    #[test]
+    fn for_each_deal_works() {
+        let cards: Vec<Card> = (2..6).map(|v| (Expedition::Red, Value::N(v)).into()).collect();
+        let mut decks: Vec<Vec<Card>> = vec![];
+        for_each_deal(&cards, 2, &mut |deck, rest| {
+            assert_eq!(2, deck.len());
+            assert_eq!(2, rest.len());
+            decks.push(deck.to_vec());
+        });
+        decks.dedup();
+        assert_eq!(6, decks.len());
+    }
+
+    #[test]
+    fn chance_works() {
+        let r5: Card = (Expedition::Red, Value::N(5)).into();
+        let rx: Card = (Expedition::Red, Value::Investment).into();
+        let value = chance(&[rx, r5, rx], |c| Ok(if c == r5 { 3.0 } else { 0.0 })).unwrap();
+        assert_eq!(1.0, value);
+    }
+
+    #[test]
+    fn known_node_buries_discards() {
+        let mut game = endgame();
+        let y3: Card = (Expedition::Yellow, Value::N(3)).into();
+        let y4: Card = (Expedition::Yellow, Value::N(4)).into();
+        game.expeditions[1].retain(|&c| c != y3 && c != y4);
+        game.discards = vec![y3, y4];
+        let node = known_node(&game.player_state(0));
+        assert_eq!(vec![y4], node.game.discards);
+        assert_eq!(vec![y3], node.buried);
+        assert!(Solver::default().solve(&game.player_state(0)).is_ok());
+        game.options.open_discards = true;
+        let node = known_node(&game.player_state(0));
+        assert_eq!(vec![y3, y4], node.game.discards);
+        assert!(node.buried.is_empty());
+    }
+
+    #[test]
+    fn solve_works() {
+        let game = endgame();
+        let state = game.player_state(0);
+        assert_eq!(4, state.unseen.values().map(|u| u.len()).sum::<usize>());
+        let values = Solver::default().solve(&state).unwrap();
+        assert_eq!(game.legal_moves(0).len(), values.len());
+        // Playing R10 onto R9 is clearly the best move.
+        assert_eq!(
+            Command::Play((Expedition::Red, Value::N(10)).into()),
+            values[0].command
+        );
+        let r = regret(
+            &values,
+            Command::Discard((Expedition::Red, Value::N(10)).into()),
+        ).unwrap();
+        assert!(r.loss >= 10.0, "{}", r);
+        assert!(r.to_string().starts_with("discarding R10 instead of playing R10 loses"));
+    }
+
+    #[test]
+    fn solve_uses_open_information() {
+        let mut game = endgame();
+        game.options.open_information = true;
+        let values = Solver::default().solve(&game.player_state(0)).unwrap();
+        assert_eq!(
+            Command::Play((Expedition::Red, Value::N(10)).into()),
+            values[0].command
+        );
+    }
+
+    #[test]
+    fn solve_rejects_large_decks() {
+        let game = Game::new_with_seed(2, 1).unwrap().0;
+        assert!(Solver::default().solve(&game.player_state(0)).is_err());
+        assert!(Solver::default().solve(&endgame().player_state(1)).is_err());
+        let options = GameOptions {
+            open_information: true,
+            ..GameOptions::default()
+        };
+        let game = Game::new_with_options(2, options, 1).unwrap().0;
+        assert!(Solver::default().solve(&game.player_state(0)).is_err());
+    }
+}
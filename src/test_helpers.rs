@@ -0,0 +1,8 @@
+//! Helpers shared between the unit tests of different modules.
+
+use crate::card::{Card, Expedition, Value};
+
+/// A numbered card.
+pub fn card(e: Expedition, v: usize) -> Card {
+    (e, Value::N(v)).into()
+}
@@ -0,0 +1,298 @@
+use std::fmt;
+
+use brdgme_game::Gamer;
+
+use crate::card::{can_play, of_expedition, Card, Expedition};
+use crate::{Game, Phase, START_ROUND};
+
+/// An inconsistency found in a game's state.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+    /// The options can't be played with the number of players.
+    Options(String),
+    /// The per player vectors don't all have an entry for each player.
+    PlayerCount { field: &'static str, found: usize },
+    /// A card appears a different number of times than in the initial deck.
+    CardCount {
+        card: Card,
+        expected: usize,
+        found: usize,
+    },
+    HandSize {
+        player: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// An expedition has a card played after a higher one, or an investment
+    /// after a number.
+    ExpeditionOrder {
+        player: usize,
+        expedition: Expedition,
+    },
+    /// A player doesn't have a score for each completed round.
+    ScoreRounds {
+        player: usize,
+        expected: usize,
+        found: usize,
+    },
+    CurrentPlayer(usize),
+    /// The just discarded expedition is set when it shouldn't be, or doesn't
+    /// match the last discard.
+    DiscardedExpedition(Expedition),
+    /// A revealed card isn't in the player's hand.
+    RevealedCard { player: usize, card: Card },
+    /// The rival in a solo game has an expedition out of order.
+    RivalExpeditionOrder(Expedition),
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Violation::Options(ref message) => write!(f, "invalid options: {}", message),
+            Violation::PlayerCount { field, found } => {
+                write!(f, "{} has {} entries, expected one per player", field, found)
+            }
+            Violation::CardCount {
+                card,
+                expected,
+                found,
+            } => write!(f, "found {} of {}, expected {}", found, card, expected),
+            Violation::HandSize {
+                player,
+                expected,
+                found,
+            } => write!(
+                f,
+                "player {} has {} cards in hand, expected {}",
+                player, found, expected
+            ),
+            Violation::ExpeditionOrder { player, expedition } => write!(
+                f,
+                "player {}'s {} expedition isn't in ascending order",
+                player,
+                expedition.name()
+            ),
+            Violation::ScoreRounds {
+                player,
+                expected,
+                found,
+            } => write!(
+                f,
+                "player {} has {} round scores, expected {}",
+                player, found, expected
+            ),
+            Violation::CurrentPlayer(p) => write!(f, "current player {} isn't in the game", p),
+            Violation::DiscardedExpedition(e) => write!(
+                f,
+                "discarded expedition {} doesn't match the phase or discards",
+                e.name()
+            ),
+            Violation::RevealedCard { player, card } => write!(
+                f,
+                "player {} has revealed {} but it isn't in their hand",
+                player, card
+            ),
+            Violation::RivalExpeditionOrder(e) => write!(
+                f,
+                "the rival's {} expedition isn't in ascending order",
+                e.name()
+            ),
+        }
+    }
+}
+
+impl Game {
+    /// Check the game state is consistent, returning every violation found.
+    pub fn validate(&self) -> Vec<Violation> {
+        let mut violations = vec![];
+        if let Err(e) = self.options.validate(self.players) {
+            violations.push(Violation::Options(e.to_string()));
+        }
+        for &(field, found) in &[
+            ("hands", self.hands.len()),
+            ("expeditions", self.expeditions.len()),
+            ("scores", self.scores.len()),
+            ("stats", self.stats.len()),
+        ] {
+            if found != self.players {
+                violations.push(Violation::PlayerCount { field, found });
+            }
+        }
+        if self.current_player >= self.players {
+            violations.push(Violation::CurrentPlayer(self.current_player));
+        }
+        self.validate_cards(&mut violations);
+        self.validate_hands(&mut violations);
+        for (p, exp) in self.expeditions.iter().enumerate() {
+            for e in self.options.expeditions() {
+                if !ascending(&of_expedition(exp, e)) {
+                    violations.push(Violation::ExpeditionOrder {
+                        player: p,
+                        expedition: e,
+                    });
+                }
+            }
+        }
+        if let Some(ref rival) = self.rival {
+            for e in self.options.expeditions() {
+                if !ascending(&of_expedition(&rival.expeditions, e)) {
+                    violations.push(Violation::RivalExpeditionOrder(e));
+                }
+            }
+        }
+        let completed = self.round.saturating_sub(START_ROUND);
+        for (p, s) in self.scores.iter().enumerate() {
+            if s.len() != completed {
+                violations.push(Violation::ScoreRounds {
+                    player: p,
+                    expected: completed,
+                    found: s.len(),
+                });
+            }
+        }
+        if let Some(e) = self.discarded_expedition {
+            if self.phase != Phase::DrawOrTake
+                || self.discards.last().map(|c| c.expedition) != Some(e)
+            {
+                violations.push(Violation::DiscardedExpedition(e));
+            }
+        }
+        violations
+    }
+
+    fn validate_cards(&self, violations: &mut Vec<Violation>) {
+        let mut expected = self.options.initial_deck();
+        expected.sort();
+        let mut found: Vec<Card> = self.deck
+            .iter()
+            .chain(self.discards.iter())
+            .chain(self.hands.iter().flat_map(|h| h.iter()))
+            .chain(self.expeditions.iter().flat_map(|e| e.iter()))
+            .chain(
+                self.rival
+                    .iter()
+                    .flat_map(|r| r.pile.iter().chain(r.expeditions.iter())),
+            )
+            .cloned()
+            .collect();
+        found.sort();
+        let mut cards = expected.clone();
+        cards.extend(found.iter().cloned());
+        cards.sort();
+        cards.dedup();
+        for c in cards {
+            let e = expected.iter().filter(|&&ec| ec == c).count();
+            let f = found.iter().filter(|&&fc| fc == c).count();
+            if e != f {
+                violations.push(Violation::CardCount {
+                    card: c,
+                    expected: e,
+                    found: f,
+                });
+            }
+        }
+    }
+
+    fn validate_hands(&self, violations: &mut Vec<Violation>) {
+        // Hands aren't refilled after the last round.
+        if self.is_finished() {
+            return;
+        }
+        for (p, h) in self.hands.iter().enumerate() {
+            let expected = if p == self.current_player && self.phase == Phase::DrawOrTake {
+                self.options.hand_size.saturating_sub(1)
+            } else {
+                self.options.hand_size
+            };
+            if h.len() != expected {
+                violations.push(Violation::HandSize {
+                    player: p,
+                    expected,
+                    found: h.len(),
+                });
+            }
+        }
+        for (p, r) in self.revealed.iter().enumerate() {
+            let mut hand = self.hands.get(p).cloned().unwrap_or_default();
+            for &c in r {
+                match hand.iter().position(|&hc| hc == c) {
+                    Some(i) => {
+                        hand.remove(i);
+                    }
+                    None => violations.push(Violation::RevealedCard { player: p, card: c }),
+                }
+            }
+        }
+    }
+}
+
+/// Whether cards of a single expedition were played in a legal order.
+fn ascending(cards: &[Card]) -> bool {
+    cards
+        .iter()
+        .enumerate()
+        .all(|(i, &c)| can_play(&cards[..i], c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::{Bot, HeuristicBot};
+    use crate::card::Value;
+
+    #[test]
+    fn validate_works() {
+        let mut game = Game::new_with_seed(2, 1).unwrap().0;
+        assert_eq!(Vec::<Violation>::new(), game.validate());
+        while !game.is_finished() {
+            let p = game.current_player;
+            let command = HeuristicBot.choose(&game.player_state(p));
+            game.apply_command(p, command).unwrap();
+            assert_eq!(Vec::<Violation>::new(), game.validate());
+        }
+    }
+
+    #[test]
+    fn validate_finds_invalid_options() {
+        let mut game = Game::new_with_seed(2, 1).unwrap().0;
+        game.options.hand_size = 0;
+        assert!(game
+            .validate()
+            .iter()
+            .any(|v| matches!(*v, Violation::Options(_))));
+    }
+
+    #[test]
+    fn validate_finds_violations() {
+        let mut game = Game::new_with_seed(2, 1).unwrap().0;
+        let c = game.hands[0].pop().unwrap();
+        game.expeditions[1] = vec![
+            (Expedition::Red, Value::N(5)).into(),
+            (Expedition::Red, Value::N(3)).into(),
+        ];
+        game.current_player = 2;
+        game.discarded_expedition = Some(Expedition::Blue);
+        game.scores[0].push(10);
+        let violations = game.validate();
+        assert!(violations.iter().any(|v| match *v {
+            Violation::CardCount { card, .. } => card == c,
+            _ => false,
+        }));
+        assert!(violations.contains(&Violation::HandSize {
+            player: 0,
+            expected: 8,
+            found: 7,
+        }));
+        assert!(violations.contains(&Violation::ExpeditionOrder {
+            player: 1,
+            expedition: Expedition::Red,
+        }));
+        assert!(violations.contains(&Violation::CurrentPlayer(2)));
+        assert!(violations.contains(&Violation::DiscardedExpedition(Expedition::Blue)));
+        assert!(violations.contains(&Violation::ScoreRounds {
+            player: 0,
+            expected: 0,
+            found: 1,
+        }));
+    }
+}